@@ -0,0 +1,423 @@
+//! NUCLEO-L496ZG board support: user LEDs and user button.
+
+use crate::clock_gate_set_clock;
+use crate::drv::{
+    clock_gate::{self, ClockId},
+    exti::{ExtiDrv, ExtiSetup, Trigger},
+    gpio::{
+        Alternate, GpioHead, GpioHeadEn, GpioPin, GpioPinHead, GpioPull, Input, Output, PushPull,
+    },
+    gpio_af::{Tim15Ch1, Tim3Ch2, Tim4Ch2},
+    led_pwm::{LedPwm, LedPwmChannel},
+    lptim::LptimDrv,
+};
+use crate::periph::{led_pwm::LedPwmPeriph, syscfg::SyscfgPeriph};
+use crate::sys::monotonic::Duration;
+use crate::thr;
+use core::marker::PhantomData;
+use drone_core::{inventory, inventory::Inventory};
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::exti::{Exti13, ExtiPeriph};
+use drone_stm32_map::periph::gpio::{
+    head::{GpioBHead, GpioCHead, GpioHeadPeriph},
+    pin::{GpioB14, GpioB7, GpioC13, GpioC7, GpioPinPeriph},
+};
+use typenum::U1;
+
+/// Acquires the [`Board`], with the user button EXTI line served by
+/// `thr.exti_15_10`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! drv_board {
+    ($reg:ident, $thr:ident) => {
+        $crate::board::Board::take(
+            $crate::board::BoardRes {
+                gpio_b_head: ::drone_stm32_map::periph::gpio::periph_gpio_b_head!($reg),
+                gpio_c_head: ::drone_stm32_map::periph::gpio::periph_gpio_c_head!($reg),
+                gpio_b7: ::drone_stm32_map::periph::gpio::periph_gpio_b7!($reg),
+                gpio_b14: ::drone_stm32_map::periph::gpio::periph_gpio_b14!($reg),
+                gpio_c7: ::drone_stm32_map::periph::gpio::periph_gpio_c7!($reg),
+                gpio_c13: ::drone_stm32_map::periph::gpio::periph_gpio_c13!($reg),
+                syscfg: $crate::periph::syscfg::periph_syscfg!($reg),
+            },
+            ::drone_stm32_map::periph::exti::periph_exti13!($reg),
+            $thr.exti_15_10,
+        )
+    };
+}
+
+/// Board resources.
+pub struct BoardRes {
+    /// GPIO port B head.
+    pub gpio_b_head: GpioHeadPeriph<GpioBHead>,
+    /// GPIO port C head.
+    pub gpio_c_head: GpioHeadPeriph<GpioCHead>,
+    /// LD1.
+    pub gpio_c7: GpioPinPeriph<GpioC7>,
+    /// LD2.
+    pub gpio_b7: GpioPinPeriph<GpioB7>,
+    /// LD3.
+    pub gpio_b14: GpioPinPeriph<GpioB14>,
+    /// Blue user button.
+    pub gpio_c13: GpioPinPeriph<GpioC13>,
+    /// SYSCFG clock, for the button EXTI port selection.
+    pub syscfg: SyscfgPeriph,
+}
+
+/// A user LED, lit while its pin is driven high.
+pub struct UserLed<T: GpioPinHead>(GpioPin<T, Output<PushPull>>);
+
+/// LD1, on PC7.
+pub type GreenLed = UserLed<GpioC7>;
+
+/// LD2, on PB7.
+pub type BlueLed = UserLed<GpioB7>;
+
+/// LD3, on PB14.
+pub type RedLed = UserLed<GpioB14>;
+
+/// A user LED driven by its timer PWM channel.
+pub struct PwmLed<'a, T: GpioPinHead> {
+    pwm: &'a LedPwm,
+    channel: LedPwmChannel,
+    _pin: PhantomData<T>,
+}
+
+/// Blue user button B1 on PC13, high when pressed, with its EXTI line.
+pub struct UserButton {
+    pin: GpioPin<GpioC13, Input>,
+    exti: ExtiDrv<Exti13, thr::Exti1510>,
+}
+
+/// NUCLEO-L496ZG board.
+pub struct Board {
+    gpio_b: Inventory<GpioHeadEn<GpioBHead>, U1>,
+    gpio_b_token: inventory::Token<GpioHeadEn<GpioBHead>>,
+    gpio_c: Inventory<GpioHeadEn<GpioCHead>, U1>,
+    gpio_c_token: inventory::Token<GpioHeadEn<GpioCHead>>,
+    ld1: GreenLed,
+    ld2: BlueLed,
+    ld3: RedLed,
+    button: UserButton,
+    syscfg: SyscfgPeriph,
+}
+
+impl Board {
+    /// Enables the GPIO ports and configures the LEDs and the user button.
+    ///
+    /// The button EXTI line triggers on both edges and starts masked; its
+    /// interrupt is enabled in the NVIC.
+    pub fn take(res: BoardRes, exti13: ExtiPeriph<Exti13>, exti_int: thr::Exti1510) -> Self {
+        let (gpio_b, gpio_b_token) = GpioHead::new(res.gpio_b_head).into_enabled_with_token();
+        let (gpio_c, gpio_c_token) = GpioHead::new(res.gpio_c_head).into_enabled_with_token();
+        let ld1 = GpioPin::new(res.gpio_c7).into_push_pull_output(&gpio_c_token);
+        let ld2 = GpioPin::new(res.gpio_b7).into_push_pull_output(&gpio_b_token);
+        let ld3 = GpioPin::new(res.gpio_b14).into_push_pull_output(&gpio_b_token);
+        let button = GpioPin::new(res.gpio_c13).into_input(&gpio_c_token, GpioPull::Down);
+        // SYSCFG holds the EXTI port selection.
+        let syscfgen = &res.syscfg.rcc_apb2enr_syscfgen;
+        clock_gate::acquire_with(
+            ClockId::Syscfg,
+            || syscfgen.read_bit(),
+            clock_gate_set_clock!(syscfgen),
+        );
+        let exti = ExtiDrv::init(ExtiSetup {
+            exti: exti13,
+            exti_int,
+            pin: &button,
+            trigger: Trigger::Both,
+        });
+        // The vector is shared by lines 10 to 15: the button line is masked
+        // on its own while not listened to.
+        exti.mask();
+        exti_int.enable_int();
+        Self {
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            ld1: UserLed(ld1),
+            ld2: UserLed(ld2),
+            ld3: UserLed(ld3),
+            button: UserButton { pin: button, exti },
+            syscfg: res.syscfg,
+        }
+    }
+
+    /// Releases resources and disables the GPIO ports. The button EXTI line
+    /// is returned masked.
+    pub fn free(self) -> (BoardRes, ExtiDrv<Exti13, thr::Exti1510>) {
+        let Self {
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            ld1,
+            ld2,
+            ld3,
+            button,
+            syscfg,
+        } = self;
+        let UserButton { pin, exti } = button;
+        exti.mask();
+        clock_gate::release_with(
+            ClockId::Syscfg,
+            clock_gate_set_clock!(syscfg.rcc_apb2enr_syscfgen),
+        );
+        // Recreated in `from_enabled()`.
+        drop(gpio_b_token);
+        drop(gpio_c_token);
+        let res = BoardRes {
+            gpio_b_head: GpioHead::from_enabled(gpio_b).free(),
+            gpio_c_head: GpioHead::from_enabled(gpio_c).free(),
+            gpio_c7: ld1.0.free(),
+            gpio_b7: ld2.0.free(),
+            gpio_b14: ld3.0.free(),
+            gpio_c13: pin.free(),
+            syscfg,
+        };
+        (res, exti)
+    }
+
+    /// Returns LD1.
+    #[inline]
+    pub fn green(&self) -> &GreenLed {
+        &self.ld1
+    }
+
+    /// Returns LD2.
+    #[inline]
+    pub fn blue(&self) -> &BlueLed {
+        &self.ld2
+    }
+
+    /// Returns LD3.
+    #[inline]
+    pub fn red(&self) -> &RedLed {
+        &self.ld3
+    }
+
+    /// Switches all LEDs at once.
+    ///
+    /// LD2 and LD3 share port B and change in the same cycle; LD1 on port C
+    /// follows immediately after.
+    pub fn set_all(&self, green: bool, blue: bool, red: bool) {
+        let (b_set, b_reset) = masks(&[(self.ld2.0.mask(), blue), (self.ld3.0.mask(), red)]);
+        let (c_set, c_reset) = masks(&[(self.ld1.0.mask(), green)]);
+        self.gpio_b.write_masked(b_set, b_reset);
+        self.gpio_c.write_masked(c_set, c_reset);
+    }
+
+    /// Returns the user button.
+    #[inline]
+    pub fn button(&self) -> &UserButton {
+        &self.button
+    }
+}
+
+/// NUCLEO-L496ZG board with the LEDs driven by timer PWM channels.
+pub struct PwmBoard {
+    gpio_b: Inventory<GpioHeadEn<GpioBHead>, U1>,
+    gpio_b_token: inventory::Token<GpioHeadEn<GpioBHead>>,
+    gpio_c: Inventory<GpioHeadEn<GpioCHead>, U1>,
+    gpio_c_token: inventory::Token<GpioHeadEn<GpioCHead>>,
+    ld1: GpioPin<GpioC7, Alternate>,
+    ld2: GpioPin<GpioB7, Alternate>,
+    ld3: GpioPin<GpioB14, Alternate>,
+    button: UserButton,
+    syscfg: SyscfgPeriph,
+    pwm: LedPwm,
+}
+
+impl Board {
+    /// Connects the LEDs to their timer channels for brightness control.
+    pub fn into_pwm(self, led_pwm: LedPwmPeriph) -> PwmBoard {
+        let Self {
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            ld1,
+            ld2,
+            ld3,
+            button,
+            syscfg,
+        } = self;
+        let pwm = LedPwm::init(led_pwm);
+        PwmBoard {
+            ld1: ld1.0.into_signal(&gpio_c_token, Tim3Ch2),
+            ld2: ld2.0.into_signal(&gpio_b_token, Tim4Ch2),
+            ld3: ld3.0.into_signal(&gpio_b_token, Tim15Ch1),
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            button,
+            syscfg,
+            pwm,
+        }
+    }
+}
+
+impl PwmBoard {
+    /// Switches the LEDs back to plain outputs, all off.
+    pub fn into_switched(self) -> (Board, LedPwmPeriph) {
+        let Self {
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            ld1,
+            ld2,
+            ld3,
+            button,
+            syscfg,
+            pwm,
+        } = self;
+        let board = Board {
+            ld1: UserLed(ld1.into_push_pull_output(&gpio_c_token)),
+            ld2: UserLed(ld2.into_push_pull_output(&gpio_b_token)),
+            ld3: UserLed(ld3.into_push_pull_output(&gpio_b_token)),
+            gpio_b,
+            gpio_b_token,
+            gpio_c,
+            gpio_c_token,
+            button,
+            syscfg,
+        };
+        board.set_all(false, false, false);
+        (board, pwm.free())
+    }
+
+    /// Returns LD1.
+    #[inline]
+    pub fn green(&self) -> PwmLed<'_, GpioC7> {
+        PwmLed::new(&self.pwm, LedPwmChannel::Tim3Ch2)
+    }
+
+    /// Returns LD2.
+    #[inline]
+    pub fn blue(&self) -> PwmLed<'_, GpioB7> {
+        PwmLed::new(&self.pwm, LedPwmChannel::Tim4Ch2)
+    }
+
+    /// Returns LD3.
+    #[inline]
+    pub fn red(&self) -> PwmLed<'_, GpioB14> {
+        PwmLed::new(&self.pwm, LedPwmChannel::Tim15Ch1)
+    }
+
+    /// Returns the user button.
+    #[inline]
+    pub fn button(&self) -> &UserButton {
+        &self.button
+    }
+}
+
+impl<T: GpioPinHead> UserLed<T> {
+    /// Switches the LED on or off.
+    #[inline]
+    pub fn set(&self, on: bool) {
+        self.0.set(on);
+    }
+
+    /// Inverts the state of the LED.
+    #[inline]
+    pub fn toggle(&self) {
+        self.0.toggle();
+    }
+
+    /// Returns `true` if the LED is on.
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.0.is_set_high()
+    }
+}
+
+impl<'a, T: GpioPinHead> PwmLed<'a, T> {
+    fn new(pwm: &'a LedPwm, channel: LedPwmChannel) -> Self {
+        Self {
+            pwm,
+            channel,
+            _pin: PhantomData,
+        }
+    }
+
+    /// Switches the LED fully on or off.
+    #[inline]
+    pub fn set(&self, on: bool) {
+        self.set_brightness(if on { 100 } else { 0 });
+    }
+
+    /// Switches the LED off if it is lit, fully on otherwise.
+    #[inline]
+    pub fn toggle(&self) {
+        self.set(!self.is_on());
+    }
+
+    /// Returns `true` if the LED is lit at all.
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.pwm.duty(self.channel) > 0
+    }
+
+    /// Sets the perceived brightness in percent.
+    #[inline]
+    pub fn set_brightness(&self, percent: u8) {
+        self.pwm.set_brightness(self.channel, percent);
+    }
+
+    /// Returns the perceived brightness in percent.
+    #[inline]
+    pub fn brightness(&self) -> u8 {
+        self.pwm.brightness(self.channel)
+    }
+
+    /// Changes the brightness to `percent` smoothly over `duration`.
+    pub async fn fade<LptimInt: IntToken>(
+        &self,
+        lptim: &LptimDrv<LptimInt>,
+        percent: u8,
+        duration: Duration,
+    ) {
+        self.pwm.fade(lptim, self.channel, percent, duration).await;
+    }
+
+    /// Fades the LED in and out with a period of `period`.
+    ///
+    /// The returned future never completes.
+    pub async fn breathe<LptimInt: IntToken>(&self, lptim: &LptimDrv<LptimInt>, period: Duration) {
+        self.pwm.breathe(lptim, self.channel, period).await;
+    }
+}
+
+impl UserButton {
+    /// Returns the input pin, e.g. to probe its level.
+    #[inline]
+    pub fn pin(&self) -> &GpioPin<GpioC13, Input> {
+        &self.pin
+    }
+
+    /// Returns the EXTI line of the button, masked until unmasked by the
+    /// listener.
+    #[inline]
+    pub fn exti(&self) -> &ExtiDrv<Exti13, thr::Exti1510> {
+        &self.exti
+    }
+
+    /// Returns `true` while the button is pressed.
+    #[inline]
+    pub fn is_pressed(&self) -> bool {
+        self.pin.is_high()
+    }
+}
+
+fn masks(pins: &[(u16, bool)]) -> (u16, u16) {
+    pins.iter().fold((0, 0), |(set, reset), &(mask, on)| {
+        if on {
+            (set | mask, reset)
+        } else {
+            (set, reset | mask)
+        }
+    })
+}
@@ -14,3 +14,12 @@ pub const HSI48_CLK: u32 = 48_000_000;
 
 // HSE high speed external clock (not present on Nucleo-144)
 pub const HSE_CLK: u32 = 48_000_000;
+
+/// SRAM2 start address (also aliased at 0x2004_0000).
+///
+/// SRAM2 is kept out of the linker memory map so that its content can be
+/// retained in Standby mode.
+pub const SRAM2_BASE: usize = 0x1000_0000;
+
+/// SRAM2 size.
+pub const SRAM2_SIZE: usize = 64 * 1024;
@@ -0,0 +1,82 @@
+//! Backup domain: write protection, VBAT charging and domain reset.
+
+use crate::periph::backup::BackupPeriph;
+use drone_cortexm::reg::prelude::*;
+
+/// VBAT battery charging through VDD (PWR_CR4.VBE and VBRS).
+///
+/// Charging must only be enabled when a rechargeable battery or a
+/// supercapacitor is connected to VBAT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VbatCharging {
+    /// No charging.
+    Disabled,
+    /// Charging through a 5 kOhm resistor.
+    Enabled5k,
+    /// Charging through a 1.5 kOhm resistor.
+    Enabled1k5,
+}
+
+/// Backup domain driver.
+pub struct Backup {
+    periph: BackupPeriph,
+}
+
+impl Backup {
+    /// Creates a new [`Backup`].
+    #[inline]
+    pub fn new(periph: BackupPeriph) -> Self {
+        Self { periph }
+    }
+
+    /// Releases the peripheral.
+    #[inline]
+    pub fn free(self) -> BackupPeriph {
+        self.periph
+    }
+
+    /// Enables or disables write access to the backup domain (PWR_CR1.DBP).
+    ///
+    /// Access is required to configure the LSE and the RTC. The PWR clock
+    /// must be held.
+    pub fn set_access(&self, enabled: bool) {
+        if enabled {
+            self.periph.pwr_cr1_dbp.set_bit();
+        } else {
+            self.periph.pwr_cr1_dbp.clear_bit();
+        }
+    }
+
+    /// Returns `true` if write access to the backup domain is enabled. The
+    /// PWR clock must be held, the bit reads as cleared otherwise.
+    pub fn is_access_enabled(&self) -> bool {
+        self.periph.pwr_cr1_dbp.read_bit()
+    }
+
+    /// Selects VBAT battery charging. The PWR clock must be held.
+    pub fn set_vbat_charging(&self, charging: VbatCharging) {
+        match charging {
+            VbatCharging::Disabled => {
+                self.periph.pwr_cr4_vbe.clear_bit();
+            }
+            VbatCharging::Enabled5k => {
+                self.periph.pwr_cr4_vbrs.clear_bit();
+                self.periph.pwr_cr4_vbe.set_bit();
+            }
+            VbatCharging::Enabled1k5 => {
+                self.periph.pwr_cr4_vbrs.set_bit();
+                self.periph.pwr_cr4_vbe.set_bit();
+            }
+        }
+    }
+
+    /// Resets the backup domain: LSE, RTC and backup registers.
+    ///
+    /// Write access must be enabled with [`Backup::set_access`] first, and
+    /// the PWR clock must be held.
+    pub fn reset_domain(&self) {
+        assert!(self.is_access_enabled(), "backup domain is write protected");
+        self.periph.rcc_bdcr_bdrst.set_bit();
+        self.periph.rcc_bdcr_bdrst.clear_bit();
+    }
+}
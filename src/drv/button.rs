@@ -0,0 +1,72 @@
+//! Push button gestures fed from an EXTI edge stream.
+
+use crate::{
+    drv::{exti::ExtiEvent, lptim::LptimDrv},
+    sys::{
+        gestures::{ButtonGestures, Gesture, GestureConfig},
+        monotonic::Instant,
+    },
+};
+use drone_cortexm::thr::prelude::*;
+use futures::prelude::*;
+use futures::{pin_mut, select_biased};
+
+/// Button driver.
+///
+/// Edges come from [`ExtiDrv::create_edge_stream`](crate::drv::exti::ExtiDrv::create_edge_stream),
+/// which timestamps them with the same LPTIM1 time base.
+pub struct ButtonDrv<'a, S: Stream<Item = ExtiEvent> + Unpin, LptimInt: IntToken> {
+    edges: S,
+    lptim: &'a LptimDrv<LptimInt>,
+    gestures: ButtonGestures,
+}
+
+impl<'a, S: Stream<Item = ExtiEvent> + Unpin, LptimInt: IntToken> ButtonDrv<'a, S, LptimInt> {
+    /// Creates a new [`ButtonDrv`] for a button high while pressed.
+    pub fn new(edges: S, lptim: &'a LptimDrv<LptimInt>, config: GestureConfig) -> Self {
+        Self {
+            edges,
+            lptim,
+            gestures: ButtonGestures::new(config),
+        }
+    }
+
+    /// Returns the gesture recognizer.
+    pub fn gestures(&self) -> &ButtonGestures {
+        &self.gestures
+    }
+
+    /// Waits for the next gesture, or until `until`.
+    pub async fn next(&mut self, until: Instant) -> Option<Gesture> {
+        loop {
+            let now = self.lptim.now();
+            if let Some(gesture) = self.gestures.poll(now.as_millis()) {
+                return Some(gesture);
+            }
+            if now >= until {
+                return None;
+            }
+            let deadline = self.gestures.deadline().map_or(until, |deadline| {
+                Instant::from_micros(deadline * 1_000).min(until)
+            });
+            let event = {
+                let tick = self.lptim.sleep_until(deadline).fuse();
+                pin_mut!(tick);
+                select_biased! {
+                    e = self.edges.next().fuse() => Some(e.expect("button stream ended")),
+                    () = tick => None,
+                }
+            };
+            if let Some(ExtiEvent {
+                level, timestamp, ..
+            }) = event
+            {
+                // The level sampled in the interrupt, rather than the edge:
+                // edges of a bouncing contact may be missed.
+                if let Some(gesture) = self.gestures.edge(level, timestamp.as_millis()) {
+                    return Some(gesture);
+                }
+            }
+        }
+    }
+}
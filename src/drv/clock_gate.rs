@@ -0,0 +1,148 @@
+//! Reference counted peripheral clock gating.
+//!
+//! Drivers sharing a peripheral clock [`acquire`] it before use and
+//! [`release`] it when done. The clock is gated off only when the last
+//! reference is released. A clock already enabled on the first acquisition,
+//! e.g. by a bootloader or before a soft reset, is taken over as is.
+
+use crate::{drv::common::DrvRcc, sys::critical};
+use core::sync::atomic::{AtomicU16, Ordering};
+
+/// Returns a `set_clock` closure for [`acquire_with`] and [`release_with`]
+/// toggling the RCC enable bit `en`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! clock_gate_set_clock {
+    ($en:expr) => {
+        |enabled| {
+            if enabled {
+                $en.set_bit();
+            } else {
+                $en.clear_bit();
+            }
+        }
+    };
+}
+
+/// Peripheral clocks tracked by the clock gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ClockId {
+    /// GPIOA (RCC_AHB2ENR.GPIOAEN).
+    GpioA,
+    /// GPIOB (RCC_AHB2ENR.GPIOBEN).
+    GpioB,
+    /// GPIOC (RCC_AHB2ENR.GPIOCEN).
+    GpioC,
+    /// GPIOD (RCC_AHB2ENR.GPIODEN).
+    GpioD,
+    /// GPIOE (RCC_AHB2ENR.GPIOEEN).
+    GpioE,
+    /// GPIOF (RCC_AHB2ENR.GPIOFEN).
+    GpioF,
+    /// GPIOG (RCC_AHB2ENR.GPIOGEN).
+    GpioG,
+    /// GPIOH (RCC_AHB2ENR.GPIOHEN).
+    GpioH,
+    /// GPIOI (RCC_AHB2ENR.GPIOIEN).
+    GpioI,
+    /// Power interface (RCC_APB1ENR1.PWREN).
+    Pwr,
+    /// LPTIM1 (RCC_APB1ENR1.LPTIM1EN).
+    Lptim1,
+    /// System configuration controller (RCC_APB2ENR.SYSCFGEN).
+    Syscfg,
+    /// TIM2 (RCC_APB1ENR1.TIM2EN).
+    Tim2,
+    /// TIM3 (RCC_APB1ENR1.TIM3EN).
+    Tim3,
+    /// TIM4 (RCC_APB1ENR1.TIM4EN).
+    Tim4,
+    /// TIM5 (RCC_APB1ENR1.TIM5EN).
+    Tim5,
+    /// TIM8 (RCC_APB2ENR.TIM8EN).
+    Tim8,
+    /// TIM15 (RCC_APB2ENR.TIM15EN).
+    Tim15,
+}
+
+impl ClockId {
+    /// All tracked clocks.
+    pub const ALL: [Self; COUNT] = [
+        Self::GpioA,
+        Self::GpioB,
+        Self::GpioC,
+        Self::GpioD,
+        Self::GpioE,
+        Self::GpioF,
+        Self::GpioG,
+        Self::GpioH,
+        Self::GpioI,
+        Self::Pwr,
+        Self::Lptim1,
+        Self::Syscfg,
+        Self::Tim2,
+        Self::Tim3,
+        Self::Tim4,
+        Self::Tim5,
+        Self::Tim8,
+        Self::Tim15,
+    ];
+}
+
+const COUNT: usize = 18;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_REFS: AtomicU16 = AtomicU16::new(0);
+
+static REFS: [AtomicU16; COUNT] = [NO_REFS; COUNT];
+
+/// Takes a reference to the clock of `drv`, enabling it if needed.
+pub fn acquire<T: DrvRcc + ?Sized>(drv: &T) {
+    acquire_with(
+        drv.clock_id(),
+        || drv.is_clock_enabled(),
+        |enabled| drv.set_clock(enabled),
+    );
+}
+
+/// Drops a reference to the clock of `drv`, disabling it with the last one.
+pub fn release<T: DrvRcc + ?Sized>(drv: &T) {
+    release_with(drv.clock_id(), |enabled| drv.set_clock(enabled));
+}
+
+/// Takes a reference to the clock `id`, for drivers without a [`DrvRcc`]
+/// implementation. `is_enabled` and `set_clock` access its RCC enable bit.
+pub fn acquire_with(id: ClockId, is_enabled: impl FnOnce() -> bool, set_clock: impl FnOnce(bool)) {
+    let refs = &REFS[id as usize];
+    critical::free(|| {
+        if refs.load(Ordering::Relaxed) == 0 && !is_enabled() {
+            set_clock(true);
+        }
+        refs.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+/// Drops a reference to the clock `id`, see [`acquire_with`].
+pub fn release_with(id: ClockId, set_clock: impl FnOnce(bool)) {
+    let refs = &REFS[id as usize];
+    critical::free(|| {
+        match refs.load(Ordering::Relaxed) {
+            0 => panic!("{:?} clock released more often than acquired", id),
+            1 => set_clock(false),
+            _ => {}
+        }
+        refs.fetch_sub(1, Ordering::Relaxed);
+    });
+}
+
+/// Returns the number of references to the clock `id`.
+#[inline]
+pub fn refs(id: ClockId) -> u16 {
+    REFS[id as usize].load(Ordering::Relaxed)
+}
+
+/// Returns the clocks currently held by at least one driver.
+pub fn clocked() -> impl Iterator<Item = ClockId> {
+    ClockId::ALL.iter().copied().filter(|&id| refs(id) > 0)
+}
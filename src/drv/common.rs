@@ -14,6 +14,7 @@
 use crate::dma::mux::DmamuxChEn;
 #[cfg(feature = "dma")]
 use crate::dma::DmaChEn;
+use crate::drv::clock_gate::ClockId;
 #[allow(unused_imports)]
 use drone_cortexm::thr::prelude::*;
 #[cfg(feature = "dma")]
@@ -31,6 +32,18 @@ pub trait DrvRcc {
     /// Enables the peripheral clocks by the clock gating during Sleep and Stop
     /// modes.
     fn enable_stop_mode(&self);
+
+    /// Returns the clock gate of the peripheral.
+    fn clock_id(&self) -> ClockId;
+
+    /// Enables or disables the peripheral clock.
+    ///
+    /// Drivers should use [`clock_gate`](crate::drv::clock_gate) instead, so
+    /// that shared clocks stay enabled.
+    fn set_clock(&self, enabled: bool);
+
+    /// Returns `true` if the peripheral clock is enabled.
+    fn is_clock_enabled(&self) -> bool;
 }
 
 /// Driver clock source selection.
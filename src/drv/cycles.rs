@@ -0,0 +1,83 @@
+//! DWT cycle counter.
+//!
+//! CYCCNT counts the core clock cycles. The conversions from and to
+//! microseconds use the HCLK frequency of the current clock configuration,
+//! see [`System::hclk`], so they stay correct across clock switches. The
+//! counter wraps after 2^32 cycles, about 54 s at 80 MHz.
+
+use crate::periph::cycles::CyclesPeriph;
+use crate::sys::system::System;
+use drone_cortexm::reg::prelude::*;
+
+/// Result of [`Cycles::measure`].
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    /// Elapsed core clock cycles.
+    pub cycles: u32,
+    /// Elapsed time in microseconds, at the HCLK frequency at the end of the
+    /// measurement.
+    pub micros: u32,
+}
+
+/// Cycle counter driver.
+pub struct Cycles {
+    periph: CyclesPeriph,
+}
+
+impl Cycles {
+    /// Enables the trace unit and starts the cycle counter.
+    pub fn init(periph: CyclesPeriph) -> Self {
+        periph.scb_demcr_trcena.set_bit();
+        periph.dwt_cyccnt.reset();
+        periph.dwt_ctrl_cyccntena.set_bit();
+        Self { periph }
+    }
+
+    /// Stops the cycle counter and releases the peripheral.
+    pub fn free(self) -> CyclesPeriph {
+        self.periph.dwt_ctrl_cyccntena.clear_bit();
+        self.periph
+    }
+
+    /// Returns the current counter value.
+    #[inline]
+    pub fn now(&self) -> u32 {
+        self.periph.dwt_cyccnt.load_bits()
+    }
+
+    /// Busy waits for `cycles` core clock cycles.
+    #[inline]
+    pub fn delay_cycles(&self, cycles: u32) {
+        let start = self.now();
+        while self.now().wrapping_sub(start) < cycles {}
+    }
+
+    /// Busy waits for `us` microseconds.
+    ///
+    /// Meant for short waits, e.g. bit-banged protocols. Use the timer queue
+    /// for anything longer.
+    #[inline]
+    pub fn delay_us(&self, us: u32) {
+        self.delay_cycles(cycles_from_micros(us, System::hclk()));
+    }
+
+    /// Runs `f` and returns its result along with its duration.
+    pub fn measure<R>(&self, f: impl FnOnce() -> R) -> (R, Measurement) {
+        let start = self.now();
+        let result = f();
+        let cycles = self.now().wrapping_sub(start);
+        let micros = micros_from_cycles(cycles, System::hclk());
+        (result, Measurement { cycles, micros })
+    }
+}
+
+/// Converts microseconds to cycles at `hclk`, saturating.
+pub fn cycles_from_micros(us: u32, hclk: u32) -> u32 {
+    let cycles = u64::from(us) * u64::from(hclk) / 1_000_000;
+    cycles.min(u64::from(u32::MAX)) as u32
+}
+
+/// Converts cycles to microseconds at `hclk`.
+pub fn micros_from_cycles(cycles: u32, hclk: u32) -> u32 {
+    (u64::from(cycles) * 1_000_000 / u64::from(hclk)) as u32
+}
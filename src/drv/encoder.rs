@@ -0,0 +1,405 @@
+//! Quadrature rotary encoders on a timer in encoder interface mode.
+//!
+//! The timer counts both edges of both channels. Its auto-reload value is
+//! set to the counts of one detent, so that the update interrupt fires once
+//! per detent. The counter starts halfway, assuming that the knob rests on a
+//! detent at init: the interrupt then fires between two detents, away from
+//! the contact bounces at rest.
+
+use crate::{
+    clock_gate_set_clock,
+    drv::{
+        clock_gate::{self, ClockId},
+        exti::ExtiEvent,
+        gpio::{Alternate, GpioPin},
+        gpio_af::{
+            GpioPinAf, Tim2Ch1, Tim2Ch2, Tim3Ch1, Tim3Ch2, Tim4Ch1, Tim4Ch2, Tim5Ch1, Tim5Ch2,
+            Tim8Ch1, Tim8Ch2,
+        },
+    },
+    periph::encoder::{
+        EncoderTim2Periph, EncoderTim3Periph, EncoderTim4Periph, EncoderTim5Periph,
+        EncoderTim8Periph,
+    },
+    sys::monotonic::Instant,
+    thr,
+};
+use core::sync::atomic::{AtomicI32, Ordering};
+use drone_core::reg::tag::Crt;
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg::{tim2, tim3, tim4, tim5, tim8};
+use futures::prelude::*;
+use futures::stream;
+
+/// Capacity of the detent ring buffer.
+const DETENT_STREAM_CAPACITY: usize = 16;
+
+/// Timer peripheral usable in encoder interface mode.
+pub trait EncoderTim: Send + 'static {
+    /// Channel 1 signal.
+    type Ch1;
+    /// Channel 2 signal.
+    type Ch2;
+
+    /// Returns the detent counter, updated in the update interrupt.
+    fn detents() -> &'static AtomicI32;
+
+    /// Acquires the timer clock and starts the timer.
+    fn start(&self, filter: u32, counts_per_detent: u32);
+
+    /// Stops the timer and releases its clock.
+    fn stop(&self);
+
+    /// Returns the counter value.
+    fn count(&self) -> u32;
+
+    /// Returns `true` while the counter counts down.
+    fn is_counting_down(&self) -> bool;
+
+    /// Clears the update flag and returns the counter value if it was set.
+    /// Only called from the update interrupt.
+    fn take_update() -> Option<u32>;
+}
+
+/// Marker for the interrupt of an encoder timer.
+pub trait EncoderVector<TimInt: IntToken> {}
+
+macro_rules! encoder_tim {
+    (
+        $periph:ident,
+        $tim:ident,
+        $int:ident,
+        $ch1:ident,
+        $ch2:ident,
+        $clock:ident,
+        $timen:ident,
+        $cr1:ident,
+        $dier:ident,
+        $sr:ident,
+        $smcr:ident,
+        $ccmr1:ident,
+        $ccer:ident,
+        $cnt:ident,
+        $psc:ident,
+        $arr:ident,
+    ) => {
+        impl EncoderTim for $periph {
+            type Ch1 = $ch1;
+            type Ch2 = $ch2;
+
+            fn detents() -> &'static AtomicI32 {
+                static DETENTS: AtomicI32 = AtomicI32::new(0);
+                &DETENTS
+            }
+
+            fn start(&self, filter: u32, counts_per_detent: u32) {
+                clock_gate::acquire_with(
+                    ClockId::$clock,
+                    || self.$timen.read_bit(),
+                    clock_gate_set_clock!(self.$timen),
+                );
+                // Encoder mode 3: counts on both TI1 and TI2 edges.
+                self.$smcr.store(|r| r.write_sms(0b011));
+                self.$ccmr1.store(|r| {
+                    r.write_cc1s(0b01)
+                        .write_ic1f(filter)
+                        .write_cc2s(0b01)
+                        .write_ic2f(filter)
+                });
+                self.$ccer.reset();
+                self.$psc.reset();
+                self.$arr.store_bits(counts_per_detent - 1);
+                self.$cnt.store_bits(counts_per_detent / 2);
+                self.$sr.reset();
+                self.$dier.store(|r| r.set_uie());
+                self.$cr1.store(|r| r.set_cen());
+            }
+
+            fn stop(&self) {
+                self.$cr1.reset();
+                self.$dier.reset();
+                clock_gate::release_with(ClockId::$clock, clock_gate_set_clock!(self.$timen));
+            }
+
+            #[inline]
+            fn count(&self) -> u32 {
+                self.$cnt.load_bits()
+            }
+
+            #[inline]
+            fn is_counting_down(&self) -> bool {
+                self.$cr1.load().dir()
+            }
+
+            fn take_update() -> Option<u32> {
+                let sr = unsafe { $tim::Sr::<Crt>::take() };
+                if sr.load().uif() {
+                    sr.modify(|r| r.clear_uif());
+                    Some(unsafe { $tim::Cnt::<Crt>::take() }.load_bits())
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl EncoderVector<thr::$int> for $periph {}
+    };
+}
+
+encoder_tim!(
+    EncoderTim2Periph,
+    tim2,
+    Tim2,
+    Tim2Ch1,
+    Tim2Ch2,
+    Tim2,
+    rcc_apb1enr1_tim2en,
+    tim2_cr1,
+    tim2_dier,
+    tim2_sr,
+    tim2_smcr,
+    tim2_ccmr1_input,
+    tim2_ccer,
+    tim2_cnt,
+    tim2_psc,
+    tim2_arr,
+);
+encoder_tim!(
+    EncoderTim3Periph,
+    tim3,
+    Tim3,
+    Tim3Ch1,
+    Tim3Ch2,
+    Tim3,
+    rcc_apb1enr1_tim3en,
+    tim3_cr1,
+    tim3_dier,
+    tim3_sr,
+    tim3_smcr,
+    tim3_ccmr1_input,
+    tim3_ccer,
+    tim3_cnt,
+    tim3_psc,
+    tim3_arr,
+);
+encoder_tim!(
+    EncoderTim4Periph,
+    tim4,
+    Tim4,
+    Tim4Ch1,
+    Tim4Ch2,
+    Tim4,
+    rcc_apb1enr1_tim4en,
+    tim4_cr1,
+    tim4_dier,
+    tim4_sr,
+    tim4_smcr,
+    tim4_ccmr1_input,
+    tim4_ccer,
+    tim4_cnt,
+    tim4_psc,
+    tim4_arr,
+);
+encoder_tim!(
+    EncoderTim5Periph,
+    tim5,
+    Tim5,
+    Tim5Ch1,
+    Tim5Ch2,
+    Tim5,
+    rcc_apb1enr1_tim5en,
+    tim5_cr1,
+    tim5_dier,
+    tim5_sr,
+    tim5_smcr,
+    tim5_ccmr1_input,
+    tim5_ccer,
+    tim5_cnt,
+    tim5_psc,
+    tim5_arr,
+);
+encoder_tim!(
+    EncoderTim8Periph,
+    tim8,
+    Tim8Up,
+    Tim8Ch1,
+    Tim8Ch2,
+    Tim8,
+    rcc_apb2enr_tim8en,
+    tim8_cr1,
+    tim8_dier,
+    tim8_sr,
+    tim8_smcr,
+    tim8_ccmr1_input,
+    tim8_ccer,
+    tim8_cnt,
+    tim8_psc,
+    tim8_arr,
+);
+
+/// Encoder setup.
+pub struct EncoderSetup<
+    Tim: EncoderTim,
+    TimInt: IntToken,
+    Ch1: GpioPinAf<Tim::Ch1>,
+    Ch2: GpioPinAf<Tim::Ch2>,
+> {
+    /// Timer peripheral, e.g. from `periph_encoder_tim3!`.
+    pub tim: Tim,
+    /// Timer update interrupt. The caller enables it in the NVIC, e.g. with
+    /// `thr.tim_3.enable_int()`.
+    pub tim_int: TimInt,
+    /// Channel 1 pin, connected with [`GpioPin::into_signal`].
+    pub ch1: GpioPin<Ch1, Alternate>,
+    /// Channel 2 pin.
+    pub ch2: GpioPin<Ch2, Alternate>,
+    /// Counter steps per detent, usually 4.
+    pub counts_per_detent: u32,
+    /// Input filter (ICxF), from 0 (off) to 15.
+    pub filter: u32,
+}
+
+/// Turn direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Counter counts up.
+    Clockwise,
+    /// Counter counts down.
+    CounterClockwise,
+}
+
+/// Encoder event.
+#[derive(Clone, Copy, Debug)]
+pub enum EncoderEvent {
+    /// The knob moved by this many detents, positive clockwise.
+    Turn(i32),
+    /// The push switch changed, from the EXTI driver.
+    Switch(ExtiEvent),
+}
+
+/// Rotary encoder driver.
+pub struct EncoderDrv<
+    Tim: EncoderTim,
+    TimInt: IntToken,
+    Ch1: GpioPinAf<Tim::Ch1>,
+    Ch2: GpioPinAf<Tim::Ch2>,
+> {
+    setup: EncoderSetup<Tim, TimInt, Ch1, Ch2>,
+    last_position: i32,
+    last_sample: Instant,
+}
+
+impl<Tim: EncoderTim, TimInt: IntToken, Ch1: GpioPinAf<Tim::Ch1>, Ch2: GpioPinAf<Tim::Ch2>>
+    EncoderDrv<Tim, TimInt, Ch1, Ch2>
+{
+    /// Sets up a new [`EncoderDrv`] from `setup` values, at position 0.
+    ///
+    /// The detents are only counted once `tim_int` is enabled by the caller.
+    pub fn init(setup: EncoderSetup<Tim, TimInt, Ch1, Ch2>) -> Self
+    where
+        Tim: EncoderVector<TimInt>,
+    {
+        assert!(setup.counts_per_detent >= 2, "invalid counts per detent");
+        assert!(setup.filter <= 15, "invalid input filter");
+        Tim::detents().store(0, Ordering::Release);
+        setup.tim.start(setup.filter, setup.counts_per_detent);
+        let half = setup.counts_per_detent / 2;
+        setup.tim_int.add_fib(fib::new_fn(move || {
+            update_detents::<Tim>(half);
+            fib::Yielded(())
+        }));
+        Self {
+            setup,
+            last_position: 0,
+            last_sample: Instant::default(),
+        }
+    }
+
+    /// Stops the timer and releases the underlying resources.
+    pub fn free(self) -> EncoderSetup<Tim, TimInt, Ch1, Ch2> {
+        self.setup.tim.stop();
+        self.setup
+    }
+
+    /// Returns the position in detents.
+    pub fn detents(&self) -> i32 {
+        Tim::detents().load(Ordering::Acquire)
+    }
+
+    /// Returns the position in counter steps.
+    pub fn position(&self) -> i32 {
+        loop {
+            let detents = self.detents();
+            let count = self.setup.tim.count();
+            if detents == self.detents() {
+                let offset = count as i32 - (self.setup.counts_per_detent / 2) as i32;
+                break detents * self.setup.counts_per_detent as i32 + offset;
+            }
+        }
+    }
+
+    /// Returns the direction of the last move.
+    pub fn direction(&self) -> Direction {
+        if self.setup.tim.is_counting_down() {
+            Direction::CounterClockwise
+        } else {
+            Direction::Clockwise
+        }
+    }
+
+    /// Returns the speed in counter steps per second since the previous
+    /// call at `now`.
+    pub fn velocity(&mut self, now: Instant) -> i32 {
+        let position = self.position();
+        let elapsed = (now - self.last_sample).as_micros();
+        let moved = position.wrapping_sub(self.last_position);
+        self.last_position = position;
+        self.last_sample = now;
+        if elapsed == 0 {
+            0
+        } else {
+            (i64::from(moved) * 1_000_000 / elapsed as i64) as i32
+        }
+    }
+
+    /// Creates a new saturating stream of detent changes.
+    pub fn create_stream(&self) -> impl Stream<Item = i32> + Send + Sync {
+        let half = self.setup.counts_per_detent / 2;
+        let mut seen = self.detents();
+        self.setup.tim_int.add_saturating_stream(
+            DETENT_STREAM_CAPACITY,
+            fib::new_fn(move || {
+                // Whichever fiber runs first consumes the update flag.
+                update_detents::<Tim>(half);
+                let detents = Tim::detents().load(Ordering::Acquire);
+                if detents == seen {
+                    fib::Yielded(None)
+                } else {
+                    let moved = detents.wrapping_sub(seen);
+                    seen = detents;
+                    fib::Yielded(Some(moved))
+                }
+            }),
+        )
+    }
+
+    /// Creates a new stream of detent changes merged with the push switch
+    /// `switch`, e.g. an [`ExtiDrv::create_edge_stream`](crate::drv::exti::ExtiDrv::create_edge_stream).
+    pub fn create_stream_with_switch(
+        &self,
+        switch: impl Stream<Item = ExtiEvent> + Send + Sync,
+    ) -> impl Stream<Item = EncoderEvent> + Send + Sync {
+        stream::select(
+            self.create_stream().map(EncoderEvent::Turn),
+            switch.map(EncoderEvent::Switch),
+        )
+    }
+}
+
+fn update_detents<Tim: EncoderTim>(half: u32) {
+    if let Some(count) = Tim::take_update() {
+        // Wrapped upwards to 0, or downwards to the auto-reload value.
+        let step = if count < half { 1 } else { -1 };
+        Tim::detents().fetch_add(step, Ordering::AcqRel);
+    }
+}
@@ -1,18 +1,144 @@
 use core::num::NonZeroUsize;
 //use displaydoc::Display;
-use crate::drv::exti_diverged::ExtiDiverged;
+use crate::drv::{
+    exti_diverged::ExtiDiverged,
+    gpio::{GpioHeadId, GpioPin, GpioPinHead, GpioProbe, Input},
+    lptim::LptimClock,
+};
+use crate::sys::monotonic::Instant;
 use drone_cortexm::{fib, fib::Fiber, reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::exti::{
-    ExtiFtsrFt, ExtiMap, ExtiPeriph, ExtiPrPif, ExtiRtsrRt, ExtiSwierSwi, SyscfgExticrExti,
+    Exti0, Exti1, Exti10, Exti11, Exti12, Exti13, Exti14, Exti15, Exti2, Exti3, Exti4, Exti5,
+    Exti6, Exti7, Exti8, Exti9, ExtiFtsrFt, ExtiMap, ExtiPeriph, ExtiPrPif, ExtiRtsrRt,
+    ExtiSwierSwi, SyscfgExticrExti,
 };
+use drone_stm32_map::periph::gpio::pin::*;
 use futures::prelude::*;
 
+/// EXTI line served by the interrupt `ExtiInt`.
+///
+/// Lines 5 to 9 and 10 to 15 share one vector each. Every [`ExtiDrv`] adds
+/// its own fiber to the shared thread, which only handles the pending flag
+/// of its line, so several lines can be used at once.
+pub trait ExtiVector<ExtiInt: IntToken> {}
+
+/// Implements [`ExtiVector`] for the lines `$exti` served by `thr::$int`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exti_vector {
+    ($int:ident, $($exti:ident),*) => {
+        $(
+            impl $crate::drv::exti::ExtiVector<$crate::thr::$int> for $exti {}
+        )*
+    };
+}
+
+exti_vector!(Exti0, Exti0);
+exti_vector!(Exti1, Exti1);
+exti_vector!(Exti2, Exti2);
+exti_vector!(Exti3, Exti3);
+exti_vector!(Exti4, Exti4);
+exti_vector!(Exti95, Exti5, Exti6, Exti7, Exti8, Exti9);
+exti_vector!(Exti1510, Exti10, Exti11, Exti12, Exti13, Exti14, Exti15);
+
+/// GPIO pin connected to the EXTI line `Exti` by its number.
+pub trait GpioPinExti: GpioPinHead {
+    /// The EXTI line of the pin.
+    type Exti;
+}
+
+macro_rules! exti_pin {
+    ($exti:ident, $($pin:ident),*) => {
+        $(
+            impl GpioPinExti for $pin {
+                type Exti = $exti;
+            }
+        )*
+    };
+}
+
+exti_pin!(Exti0, GpioA0, GpioB0, GpioC0, GpioD0, GpioE0, GpioF0, GpioG0, GpioH0, GpioI0);
+exti_pin!(Exti1, GpioA1, GpioB1, GpioC1, GpioD1, GpioE1, GpioF1, GpioG1, GpioH1, GpioI1);
+exti_pin!(Exti2, GpioA2, GpioB2, GpioC2, GpioD2, GpioE2, GpioF2, GpioG2, GpioH2, GpioI2);
+exti_pin!(Exti3, GpioA3, GpioB3, GpioC3, GpioD3, GpioE3, GpioF3, GpioG3, GpioH3, GpioI3);
+exti_pin!(Exti4, GpioA4, GpioB4, GpioC4, GpioD4, GpioE4, GpioF4, GpioG4, GpioH4, GpioI4);
+exti_pin!(Exti5, GpioA5, GpioB5, GpioC5, GpioD5, GpioE5, GpioF5, GpioG5, GpioH5, GpioI5);
+exti_pin!(Exti6, GpioA6, GpioB6, GpioC6, GpioD6, GpioE6, GpioF6, GpioG6, GpioH6, GpioI6);
+exti_pin!(Exti7, GpioA7, GpioB7, GpioC7, GpioD7, GpioE7, GpioF7, GpioG7, GpioH7, GpioI7);
+exti_pin!(Exti8, GpioA8, GpioB8, GpioC8, GpioD8, GpioE8, GpioF8, GpioG8, GpioH8, GpioI8);
+exti_pin!(Exti9, GpioA9, GpioB9, GpioC9, GpioD9, GpioE9, GpioF9, GpioG9, GpioH9, GpioI9);
+exti_pin!(Exti10, GpioA10, GpioB10, GpioC10, GpioD10, GpioE10, GpioF10, GpioG10, GpioH10, GpioI10);
+exti_pin!(Exti11, GpioA11, GpioB11, GpioC11, GpioD11, GpioE11, GpioF11, GpioG11, GpioH11, GpioI11);
+exti_pin!(Exti12, GpioA12, GpioB12, GpioC12, GpioD12, GpioE12, GpioF12, GpioG12, GpioH12);
+exti_pin!(Exti13, GpioA13, GpioB13, GpioC13, GpioD13, GpioE13, GpioF13, GpioG13, GpioH13);
+exti_pin!(Exti14, GpioA14, GpioB14, GpioC14, GpioD14, GpioE14, GpioF14, GpioG14, GpioH14);
+exti_pin!(Exti15, GpioA15, GpioB15, GpioC15, GpioD15, GpioE15, GpioF15, GpioG15, GpioH15);
+
+/// EXTI trigger edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Rising edge.
+    Rising,
+    /// Falling edge.
+    Falling,
+    /// Both edges.
+    Both,
+}
+
+/// Signal edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Low to high.
+    Rising,
+    /// High to low.
+    Falling,
+}
+
+/// External event with the pin state.
+#[derive(Clone, Copy, Debug)]
+pub struct ExtiEvent {
+    /// The edge, derived from the level. Software triggers are reported as
+    /// rising edges.
+    pub edge: Edge,
+    /// The pin level read in the interrupt. It may already differ from the
+    /// edge which triggered it if the signal bounces.
+    pub level: bool,
+    /// Time of the interrupt.
+    pub timestamp: Instant,
+    /// `true` if the event was fired by [`ExtiDrv::trigger`].
+    pub software: bool,
+}
+
+/// Capacity of the edge event ring buffer.
+const EDGE_STREAM_CAPACITY: usize = 16;
+
 /// EXTI stream overflow
 #[derive(Debug)]
 pub struct ExtiOverflow;
 
 /// EXTI setup.
 pub struct ExtiSetup<
+    'a,
+    Exti: ExtiMap + SyscfgExticrExti + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+    ExtiInt: IntToken,
+    Pin: GpioPinExti<Exti = Exti>,
+> {
+    /// EXTI peripheral.
+    pub exti: ExtiPeriph<Exti>,
+    /// EXTI interrupt.
+    pub exti_int: ExtiInt,
+    /// The input pin to route to the line. Its port is written to
+    /// SYSCFG_EXTICRx.EXTIy.
+    pub pin: &'a GpioPin<Pin, Input>,
+    /// Trigger edge selection.
+    pub trigger: Trigger,
+}
+
+/// EXTI setup for a line used as a software interrupt.
+///
+/// The line must not be routed to a GPIO pin used elsewhere: its edge
+/// triggers are disabled.
+pub struct ExtiSoftSetup<
     Exti: ExtiMap + SyscfgExticrExti + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
     ExtiInt: IntToken,
 > {
@@ -20,15 +146,6 @@ pub struct ExtiSetup<
     pub exti: ExtiPeriph<Exti>,
     /// EXTI interrupt.
     pub exti_int: ExtiInt,
-    /// EXTI configuration.
-    ///
-    /// This will be written to SYSCFG_EXTICRx.EXTIy field. See the reference
-    /// manual for details.
-    pub config: u32,
-    /// Falling trigger selection.
-    pub falling: bool,
-    /// Rising trigger selection.
-    pub rising: bool,
 }
 
 /// EXTI driver.
@@ -46,27 +163,124 @@ impl<
     > ExtiDrv<Exti, ExtiInt>
 {
     /// Sets up a new [`ExtiDrv`] from `setup` values.
-    pub fn init(setup: ExtiSetup<Exti, ExtiInt>) -> Self {
+    pub fn init<Pin: GpioPinExti<Exti = Exti>>(setup: ExtiSetup<'_, Exti, ExtiInt, Pin>) -> Self
+    where
+        Exti: ExtiVector<ExtiInt>,
+    {
         let ExtiSetup {
             exti,
             exti_int,
-            config,
-            falling,
-            rising,
+            pin: _,
+            trigger,
         } = setup;
         let drv = Self {
             exti: exti.into(),
             exti_int,
         };
-        drv.init_exti(config, falling, rising);
+        drv.init_exti(<Pin::Head as GpioHeadId>::PORT, trigger);
+        drv
+    }
+
+    /// Sets up a new [`ExtiDrv`] for software triggers only, e.g. to signal
+    /// a thread running at the interrupt priority from another one.
+    pub fn init_soft(setup: ExtiSoftSetup<Exti, ExtiInt>) -> Self
+    where
+        Exti: ExtiVector<ExtiInt>,
+    {
+        let ExtiSoftSetup { exti, exti_int } = setup;
+        let drv = Self {
+            exti: exti.into(),
+            exti_int,
+        };
+        drv.exti.exti_rtsr_rt.clear_bit();
+        drv.exti.exti_ftsr_ft.clear_bit();
+        drv.exti.exti_imr_im.set_bit();
         drv
     }
 
+    /// Fires the line through EXTI_SWIER, as if the selected edge occurred.
+    ///
+    /// Has no effect while the line is masked.
+    #[inline]
+    pub fn trigger(&self) {
+        self.exti.exti_swier_swi.set_bit();
+    }
+
+    /// Masks the line, leaving the other lines of a shared vector running.
+    #[inline]
+    pub fn mask(&self) {
+        self.exti.exti_imr_im.clear_bit();
+    }
+
+    /// Unmasks the line.
+    #[inline]
+    pub fn unmask(&self) {
+        self.exti.exti_imr_im.set_bit();
+    }
+
+    /// Returns `true` if the line is masked.
+    #[inline]
+    pub fn is_masked(&self) -> bool {
+        !self.exti.exti_imr_im.read_bit()
+    }
+
+    /// Enables or disables event mode (EXTI_EMR): the selected edge then
+    /// wakes the core from WFE, also in Stop mode, without a pending flag.
+    ///
+    /// Mask the line with [`ExtiDrv::mask`] to get no interrupt at all, and
+    /// wait with [`idle::wait_for_event`](crate::sys::idle::wait_for_event).
+    pub fn set_event_mode(&self, enabled: bool) {
+        if enabled {
+            self.exti.exti_emr_em.set_bit();
+        } else {
+            self.exti.exti_emr_em.clear_bit();
+        }
+    }
+
     /// Creates a new saturating stream of external events.
     pub fn create_saturating_stream(&self) -> impl Stream<Item = NonZeroUsize> + Send + Sync {
         self.exti_int.add_saturating_pulse_stream(self.new_fib())
     }
 
+    /// Creates a new saturating stream of timestamped edges of the pin read
+    /// by `probe`.
+    ///
+    /// A line supports only one stream at a time: the first one to run
+    /// clears the pending flag.
+    pub fn create_edge_stream<Pin: GpioPinExti<Exti = Exti>>(
+        &self,
+        probe: GpioProbe<Pin>,
+        clock: LptimClock,
+    ) -> impl Stream<Item = ExtiEvent> + Send + Sync {
+        let exti_pr_pif = self.exti.exti_pr_pif;
+        let exti_swier_swi = self.exti.exti_swier_swi;
+        self.exti_int.add_saturating_stream(
+            EDGE_STREAM_CAPACITY,
+            fib::new_fn(move || {
+                if exti_pr_pif.read_bit() {
+                    // SWIER is cleared together with the pending flag.
+                    let software = exti_swier_swi.read_bit();
+                    exti_pr_pif.set_bit();
+                    let timestamp = clock.now();
+                    let level = probe.is_high();
+                    let edge = if level || software {
+                        Edge::Rising
+                    } else {
+                        Edge::Falling
+                    };
+                    fib::Yielded(Some(ExtiEvent {
+                        edge,
+                        level,
+                        timestamp,
+                        software,
+                    }))
+                } else {
+                    fib::Yielded(None)
+                }
+            }),
+        )
+    }
+
     /// Creates a new fallible stream of external events.
     pub fn create_try_stream(
         &self,
@@ -76,26 +290,46 @@ impl<
     }
 
     fn new_fib<R>(&self) -> impl Fiber<Input = (), Yield = Option<usize>, Return = R> {
-        let exti_pr_pif = self.exti.exti_pr_pif;
-        fib::new_fn(move || {
-            if exti_pr_pif.read_bit() {
-                // selected trigger request occurred
-                exti_pr_pif.set_bit();
-                fib::Yielded(Some(1))
-            } else {
-                fib::Yielded(None)
-            }
-        })
+        pending_fib::<Exti, R>(self.exti.exti_pr_pif)
     }
 
-    fn init_exti(&self, config: u32, falling: bool, rising: bool) {
-        self.exti.syscfg_exticr_exti.write_bits(config); // configuration
-        self.exti.exti_imr_im.set_bit(); // interrupt request from line 4 is not masked
-        if falling {
-            self.exti.exti_ftsr_ft.set_bit(); // falling trigger enabled
-        }
-        if rising {
-            self.exti.exti_rtsr_rt.set_bit(); // rising trigger enabled
-        }
+    fn init_exti(&self, port: u32, trigger: Trigger) {
+        self.exti.syscfg_exticr_exti.write_bits(port);
+        self.exti.exti_imr_im.set_bit(); // interrupt request is not masked
+        write_trigger::<Exti>(&self.exti.exti_rtsr_rt, &self.exti.exti_ftsr_ft, trigger);
+    }
+}
+
+/// Selects the edges of `trigger` in EXTI_RTSR and EXTI_FTSR.
+pub(crate) fn write_trigger<Exti: ExtiMap + ExtiRtsrRt + ExtiFtsrFt>(
+    exti_rtsr_rt: &Exti::SExtiRtsrRt,
+    exti_ftsr_ft: &Exti::SExtiFtsrFt,
+    trigger: Trigger,
+) {
+    if trigger == Trigger::Falling || trigger == Trigger::Both {
+        exti_ftsr_ft.set_bit();
+    } else {
+        exti_ftsr_ft.clear_bit();
     }
+    if trigger == Trigger::Rising || trigger == Trigger::Both {
+        exti_rtsr_rt.set_bit();
+    } else {
+        exti_rtsr_rt.clear_bit();
+    }
+}
+
+/// Returns a fiber clearing the pending flag of a line, yielding once per
+/// event.
+pub(crate) fn pending_fib<Exti: ExtiMap + ExtiPrPif, R>(
+    exti_pr_pif: Exti::CExtiPrPif,
+) -> impl Fiber<Input = (), Yield = Option<usize>, Return = R> {
+    fib::new_fn(move || {
+        if exti_pr_pif.read_bit() {
+            // selected trigger request occurred
+            exti_pr_pif.set_bit();
+            fib::Yielded(Some(1))
+        } else {
+            fib::Yielded(None)
+        }
+    })
 }
@@ -12,7 +12,7 @@ pub(crate) struct ExtiDiverged<
     pub(crate) exti_emr_em: Exti::SExtiEmrEm,
     pub(crate) exti_rtsr_rt: Exti::SExtiRtsrRt,
     pub(crate) exti_ftsr_ft: Exti::SExtiFtsrFt,
-    pub(crate) exti_swier_swi: Exti::SExtiSwierSwi,
+    pub(crate) exti_swier_swi: Exti::CExtiSwierSwi,
     pub(crate) exti_pr_pif: Exti::CExtiPrPif,
 }
 
@@ -35,7 +35,7 @@ impl<Exti: ExtiMap + SyscfgExticrExti + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi +
             exti_emr_em,
             exti_rtsr_rt,
             exti_ftsr_ft,
-            exti_swier_swi,
+            exti_swier_swi: exti_swier_swi.into_copy(),
             exti_pr_pif: exti_pr_pif.into_copy(),
         }
     }
@@ -0,0 +1,228 @@
+//! Internal EXTI lines 16 to 40.
+//!
+//! Configurable lines (RTC, COMP, PVD/PVM) have their own edge detection and
+//! pending flag, driven by [`ExtiLineDrv`]. Direct lines (USB, U(S)ART,
+//! LPTIM wakeups) follow the level of the peripheral interrupt, which must be
+//! cleared in the peripheral itself; [`ExtiDirectDrv`] only controls the
+//! interrupt and event masks, so that the peripheral can wake the chip from
+//! Stop.
+//!
+//! Lines 16 and 35 to 38 are also used by
+//! [`PvdDrv`](crate::drv::pvd::PvdDrv); only one of both may own them.
+
+use crate::drv::exti::{pending_fib, write_trigger, ExtiOverflow, ExtiVector, Trigger};
+use crate::exti_vector;
+use core::num::NonZeroUsize;
+use drone_cortexm::{fib, fib::Fiber, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::exti::{
+    Exti16, Exti17, Exti18, Exti19, Exti20, Exti21, Exti22, Exti26, Exti27, Exti28, Exti29,
+    Exti30, Exti31, Exti32, Exti33, Exti35, Exti36, Exti37, Exti38, ExtiFtsrFt, ExtiMap,
+    ExtiPeriph, ExtiPrPif, ExtiRtsrRt, ExtiSwierSwi,
+};
+use futures::prelude::*;
+
+// Configurable lines.
+exti_vector!(PvdPvm, Exti16, Exti35, Exti36, Exti37, Exti38);
+exti_vector!(RtcAlarm, Exti18);
+exti_vector!(TampStamp, Exti19);
+exti_vector!(RtcWkup, Exti20);
+exti_vector!(Comp, Exti21, Exti22);
+
+// Direct lines. The I2C and SWPMI1 wakeup lines are not mapped yet.
+exti_vector!(OtgFs, Exti17);
+exti_vector!(Usart1, Exti26);
+exti_vector!(Usart2, Exti27);
+exti_vector!(Usart3, Exti28);
+exti_vector!(Uart4, Exti29);
+exti_vector!(Uart5, Exti30);
+exti_vector!(Lpuart1, Exti31);
+exti_vector!(Lptim1, Exti32);
+exti_vector!(Lptim2, Exti33);
+
+/// Configurable internal EXTI line setup.
+pub struct ExtiLineSetup<
+    Exti: ExtiMap + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+    ExtiInt: IntToken,
+> {
+    /// EXTI peripheral.
+    pub exti: ExtiPeriph<Exti>,
+    /// EXTI interrupt.
+    pub exti_int: ExtiInt,
+    /// Trigger edge selection.
+    pub trigger: Trigger,
+}
+
+/// Configurable internal EXTI line driver.
+pub struct ExtiLineDrv<
+    Exti: ExtiMap + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+    ExtiInt: IntToken,
+> {
+    exti_imr_im: Exti::SExtiImrIm,
+    exti_emr_em: Exti::SExtiEmrEm,
+    exti_rtsr_rt: Exti::SExtiRtsrRt,
+    exti_ftsr_ft: Exti::SExtiFtsrFt,
+    exti_swier_swi: Exti::CExtiSwierSwi,
+    exti_pr_pif: Exti::CExtiPrPif,
+    exti_int: ExtiInt,
+}
+
+/// Direct internal EXTI line setup.
+pub struct ExtiDirectSetup<Exti: ExtiMap, ExtiInt: IntToken> {
+    /// EXTI peripheral.
+    pub exti: ExtiPeriph<Exti>,
+    /// Peripheral interrupt.
+    pub exti_int: ExtiInt,
+}
+
+/// Direct internal EXTI line driver.
+pub struct ExtiDirectDrv<Exti: ExtiMap, ExtiInt: IntToken> {
+    exti_imr_im: Exti::SExtiImrIm,
+    exti_emr_em: Exti::SExtiEmrEm,
+    exti_int: ExtiInt,
+}
+
+impl<
+        Exti: ExtiMap + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+        ExtiInt: IntToken,
+    > ExtiLineDrv<Exti, ExtiInt>
+{
+    /// Sets up a new [`ExtiLineDrv`] from `setup` values.
+    pub fn init(setup: ExtiLineSetup<Exti, ExtiInt>) -> Self
+    where
+        Exti: ExtiVector<ExtiInt>,
+    {
+        let ExtiLineSetup {
+            exti,
+            exti_int,
+            trigger,
+        } = setup;
+        let ExtiPeriph {
+            exti_imr_im,
+            exti_emr_em,
+            exti_rtsr_rt,
+            exti_ftsr_ft,
+            exti_swier_swi,
+            exti_pr_pif,
+            ..
+        } = exti;
+        let drv = Self {
+            exti_imr_im,
+            exti_emr_em,
+            exti_rtsr_rt,
+            exti_ftsr_ft,
+            exti_swier_swi: exti_swier_swi.into_copy(),
+            exti_pr_pif: exti_pr_pif.into_copy(),
+            exti_int,
+        };
+        write_trigger::<Exti>(&drv.exti_rtsr_rt, &drv.exti_ftsr_ft, trigger);
+        drv.exti_imr_im.set_bit();
+        drv
+    }
+
+    /// Fires the line through EXTI_SWIER.
+    #[inline]
+    pub fn trigger(&self) {
+        self.exti_swier_swi.set_bit();
+    }
+
+    /// Masks the line interrupt.
+    #[inline]
+    pub fn mask(&self) {
+        self.exti_imr_im.clear_bit();
+    }
+
+    /// Unmasks the line interrupt.
+    #[inline]
+    pub fn unmask(&self) {
+        self.exti_imr_im.set_bit();
+    }
+
+    /// Enables or disables event mode (EXTI_EMR).
+    pub fn set_event_mode(&self, enabled: bool) {
+        if enabled {
+            self.exti_emr_em.set_bit();
+        } else {
+            self.exti_emr_em.clear_bit();
+        }
+    }
+
+    /// Creates a new saturating stream of line events.
+    pub fn create_saturating_stream(&self) -> impl Stream<Item = NonZeroUsize> + Send + Sync {
+        self.exti_int.add_saturating_pulse_stream(self.new_fib())
+    }
+
+    /// Creates a new fallible stream of line events.
+    pub fn create_try_stream(
+        &self,
+    ) -> impl Stream<Item = Result<NonZeroUsize, ExtiOverflow>> + Send + Sync {
+        self.exti_int
+            .add_pulse_try_stream(|| Err(ExtiOverflow), self.new_fib())
+    }
+
+    fn new_fib<R>(&self) -> impl Fiber<Input = (), Yield = Option<usize>, Return = R> {
+        pending_fib::<Exti, R>(self.exti_pr_pif)
+    }
+}
+
+impl<Exti: ExtiMap, ExtiInt: IntToken> ExtiDirectDrv<Exti, ExtiInt> {
+    /// Sets up a new [`ExtiDirectDrv`] from `setup` values, with the line
+    /// unmasked.
+    pub fn init(setup: ExtiDirectSetup<Exti, ExtiInt>) -> Self
+    where
+        Exti: ExtiVector<ExtiInt>,
+    {
+        let ExtiDirectSetup { exti, exti_int } = setup;
+        let ExtiPeriph {
+            exti_imr_im,
+            exti_emr_em,
+            ..
+        } = exti;
+        let drv = Self {
+            exti_imr_im,
+            exti_emr_em,
+            exti_int,
+        };
+        drv.exti_imr_im.set_bit();
+        drv
+    }
+
+    /// Masks the line: the peripheral can no longer wake the chip from Stop
+    /// through an interrupt.
+    #[inline]
+    pub fn mask(&self) {
+        self.exti_imr_im.clear_bit();
+    }
+
+    /// Unmasks the line.
+    #[inline]
+    pub fn unmask(&self) {
+        self.exti_imr_im.set_bit();
+    }
+
+    /// Enables or disables event mode (EXTI_EMR).
+    pub fn set_event_mode(&self, enabled: bool) {
+        if enabled {
+            self.exti_emr_em.set_bit();
+        } else {
+            self.exti_emr_em.clear_bit();
+        }
+    }
+
+    /// Creates a new saturating stream of wakeups.
+    ///
+    /// `take_flag` runs in the interrupt, it must check and clear the wakeup
+    /// flag in the peripheral, e.g. USART_ISR.WUF through USART_ICR.WUCF.
+    pub fn create_saturating_stream(
+        &self,
+        mut take_flag: impl FnMut() -> bool + Send + 'static,
+    ) -> impl Stream<Item = NonZeroUsize> + Send + Sync {
+        self.exti_int
+            .add_saturating_pulse_stream(fib::new_fn(move || {
+                if take_flag() {
+                    fib::Yielded(Some(1))
+                } else {
+                    fib::Yielded(None)
+                }
+            }))
+    }
+}
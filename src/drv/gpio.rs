@@ -1,20 +1,64 @@
 //! General-purpose I/O utility trait.
 
-use crate::drv::common::DrvRcc;
+use crate::drv::{
+    clock_gate::{self, ClockId},
+    common::DrvRcc,
+    gpio_af::GpioPinAf,
+};
+use core::marker::PhantomData;
 use drone_cortexm::reg::prelude::*;
-use drone_stm32_map::periph::gpio::head::{GpioHeadMap, GpioHeadPeriph};
+use drone_stm32_map::periph::gpio::{
+    head::{
+        GpioAHead, GpioBHead, GpioCHead, GpioDHead, GpioEHead, GpioFHead, GpioGHead, GpioHHead,
+        GpioHeadMap, GpioHeadPeriph, GpioIHead,
+    },
+    pin::*,
+};
 use drone_core::{inventory, inventory::Inventory};
 use typenum::{U0, U1};
 
+/// GPIO port head with a known clock gate and index.
+pub trait GpioHeadId: GpioHeadMap {
+    /// The port clock.
+    const CLOCK: ClockId;
+
+    /// The port index, as used by SYSCFG_EXTICRx: 0 for port A, 1 for B, ...
+    const PORT: u32;
+}
+
+macro_rules! gpio_head_id {
+    ($($head:ident: $clock:ident = $port:literal),*) => {
+        $(
+            impl GpioHeadId for $head {
+                const CLOCK: ClockId = ClockId::$clock;
+
+                const PORT: u32 = $port;
+            }
+        )*
+    };
+}
+
+gpio_head_id!(
+    GpioAHead: GpioA = 0,
+    GpioBHead: GpioB = 1,
+    GpioCHead: GpioC = 2,
+    GpioDHead: GpioD = 3,
+    GpioEHead: GpioE = 4,
+    GpioFHead: GpioF = 5,
+    GpioGHead: GpioG = 6,
+    GpioHHead: GpioH = 7,
+    GpioIHead: GpioI = 8
+);
+
 /// GPIO port head driver.
-pub struct GpioHead<T: GpioHeadMap>(Inventory<GpioHeadEn<T>, U0>);
+pub struct GpioHead<T: GpioHeadId>(Inventory<GpioHeadEn<T>, U0>);
 
 /// GPIO port head enabled driver.
-pub struct GpioHeadEn<T: GpioHeadMap> {
+pub struct GpioHeadEn<T: GpioHeadId> {
     periph: GpioHeadPeriph<T>,
 }
 
-impl<T: GpioHeadMap> GpioHead<T> {
+impl<T: GpioHeadId> GpioHead<T> {
     /// Creates a new [`GpioHead`].
     #[inline]
     pub fn new(periph: GpioHeadPeriph<T>) -> Self {
@@ -42,6 +86,14 @@ impl<T: GpioHeadMap> GpioHead<T> {
         enabled
     }
 
+    /// Enables the port clock, keeping the token to configure the pins.
+    pub fn into_enabled_with_token(
+        self,
+    ) -> (Inventory<GpioHeadEn<T>, U1>, inventory::Token<GpioHeadEn<T>>) {
+        self.setup();
+        self.0.share1()
+    }
+
     /// Disables the port clock.
     pub fn from_enabled(enabled: Inventory<GpioHeadEn<T>, U1>) -> Self {
         // Restoring the token dropped in `into_enabled()`.
@@ -52,15 +104,150 @@ impl<T: GpioHeadMap> GpioHead<T> {
     }
 
     fn setup(&self) {
-        let gpioen = &self.0.periph.rcc_busenr_gpioen;
-        if gpioen.read_bit() {
-            panic!("GPIO wasn't turned off");
+        clock_gate::acquire(&*self.0);
+    }
+}
+
+impl<T: GpioHeadId> GpioHeadEn<T> {
+    /// Sets the pins in `set_mask` and resets the pins in `reset_mask` with a
+    /// single BSRR write. A pin in both masks is set.
+    #[inline]
+    pub fn write_masked(&self, set_mask: u16, reset_mask: u16) {
+        self.periph
+            .gpio_bsrr
+            .store_bits(u32::from(reset_mask) << 16 | u32::from(set_mask));
+    }
+
+    /// Reads the input levels of all pins of the port at once.
+    #[inline]
+    pub fn read_all(&self) -> u16 {
+        self.periph.gpio_idr.load_bits() as u16
+    }
+
+    /// Locks the configuration of `pins` until the next reset, e.g. a pin or
+    /// a tuple of pins of this port, and returns them in their locked modes.
+    ///
+    /// The lock key sequence can succeed only once per port, so all pins to
+    /// lock must be passed at once. On failure the pins are handed back.
+    pub fn lock<P: GpioLockPins<T>>(&self, pins: P) -> Result<P::Locked, (GpioLockError, P)> {
+        const LCKK: u32 = 1 << 16;
+        let mask32 = u32::from(pins.mask());
+        if self.periph.gpio_lckr.load_bits() & LCKK != 0 {
+            return Err((GpioLockError, pins));
+        }
+        self.periph.gpio_lckr.store_bits(LCKK | mask32);
+        self.periph.gpio_lckr.store_bits(mask32);
+        self.periph.gpio_lckr.store_bits(LCKK | mask32);
+        // The first read completes the sequence, the second one confirms it.
+        self.periph.gpio_lckr.load_bits();
+        if self.periph.gpio_lckr.load_bits() & LCKK == 0 {
+            return Err((GpioLockError, pins));
+        }
+        Ok(pins.into_locked(GpioLockKey(())))
+    }
+
+    /// Returns a bus of `width` consecutive pins starting at `offset`.
+    ///
+    /// The pins must be configured separately.
+    pub fn bus(&self, offset: u32, width: u32) -> GpioBus<'_, T> {
+        assert!(width > 0 && offset + width <= 16, "invalid bus range");
+        GpioBus {
+            head: self,
+            offset,
+            mask: (((1_u32 << width) - 1) << offset) as u16,
         }
-        gpioen.set_bit();
     }
 }
 
-impl<T: GpioHeadMap> DrvRcc for GpioHead<T> {
+/// Pins of port `T` locked together by [`GpioHeadEn::lock`]: a pin in a
+/// configurable mode, or a tuple of them.
+pub trait GpioLockPins<T: GpioHeadId> {
+    /// The pins in their locked modes.
+    type Locked;
+
+    /// Returns the mask of the pins.
+    fn mask(&self) -> u16;
+
+    /// Converts the pins into their locked modes. Only [`GpioHeadEn::lock`]
+    /// holds the key.
+    fn into_locked(self, key: GpioLockKey) -> Self::Locked;
+}
+
+/// Key to [`GpioLockPins::into_locked`], which cannot be built outside of
+/// this module.
+pub struct GpioLockKey(());
+
+/// The port configuration is already locked, or the lock key sequence
+/// failed.
+#[derive(Debug)]
+pub struct GpioLockError;
+
+impl<T: GpioPinHead, M: Configurable> GpioLockPins<T::Head> for GpioPin<T, M> {
+    type Locked = GpioPin<T, Locked<M>>;
+
+    #[inline]
+    fn mask(&self) -> u16 {
+        GpioPin::mask(self)
+    }
+
+    #[inline]
+    fn into_locked(self, _key: GpioLockKey) -> Self::Locked {
+        self.into_mode()
+    }
+}
+
+macro_rules! gpio_lock_pins {
+    ($($pin:ident),*) => {
+        impl<T: GpioHeadId, $($pin: GpioLockPins<T>),*> GpioLockPins<T> for ($($pin,)*) {
+            type Locked = ($($pin::Locked,)*);
+
+            #[allow(non_snake_case)]
+            fn mask(&self) -> u16 {
+                let ($($pin,)*) = self;
+                0 $(| $pin.mask())*
+            }
+
+            #[allow(non_snake_case)]
+            fn into_locked(self, _key: GpioLockKey) -> Self::Locked {
+                let ($($pin,)*) = self;
+                ($($pin.into_locked(GpioLockKey(())),)*)
+            }
+        }
+    };
+}
+
+gpio_lock_pins!(A);
+gpio_lock_pins!(A, B);
+gpio_lock_pins!(A, B, C);
+gpio_lock_pins!(A, B, C, D);
+gpio_lock_pins!(A, B, C, D, E);
+gpio_lock_pins!(A, B, C, D, E, F);
+gpio_lock_pins!(A, B, C, D, E, F, G);
+gpio_lock_pins!(A, B, C, D, E, F, G, H);
+
+/// A group of consecutive pins of one port, read and written as an integer.
+pub struct GpioBus<'a, T: GpioHeadId> {
+    head: &'a GpioHeadEn<T>,
+    offset: u32,
+    mask: u16,
+}
+
+impl<T: GpioHeadId> GpioBus<'_, T> {
+    /// Drives the bus pins to `value` at once. Excess bits are ignored.
+    #[inline]
+    pub fn write(&self, value: u16) {
+        let value = value << self.offset & self.mask;
+        self.head.write_masked(value, !value & self.mask);
+    }
+
+    /// Reads the input levels of the bus pins.
+    #[inline]
+    pub fn read(&self) -> u16 {
+        (self.head.read_all() & self.mask) >> self.offset
+    }
+}
+
+impl<T: GpioHeadId> DrvRcc for GpioHead<T> {
     #[inline]
     fn reset(&mut self) {
         self.0.reset();
@@ -75,15 +262,30 @@ impl<T: GpioHeadMap> DrvRcc for GpioHead<T> {
     fn enable_stop_mode(&self) {
         self.0.enable_stop_mode();
     }
+
+    #[inline]
+    fn clock_id(&self) -> ClockId {
+        T::CLOCK
+    }
+
+    #[inline]
+    fn set_clock(&self, enabled: bool) {
+        self.0.set_clock(enabled);
+    }
+
+    #[inline]
+    fn is_clock_enabled(&self) -> bool {
+        self.0.is_clock_enabled()
+    }
 }
 
-impl<T: GpioHeadMap> inventory::Item for GpioHeadEn<T> {
+impl<T: GpioHeadId> inventory::Item for GpioHeadEn<T> {
     fn teardown(&mut self, _token: &mut inventory::GuardToken<Self>) {
-        self.periph.rcc_busenr_gpioen.clear_bit()
+        clock_gate::release(self);
     }
 }
 
-impl<T: GpioHeadMap> DrvRcc for GpioHeadEn<T> {
+impl<T: GpioHeadId> DrvRcc for GpioHeadEn<T> {
     fn reset(&mut self) {
         self.periph.rcc_busrstr_gpiorst.set_bit();
     }
@@ -95,4 +297,391 @@ impl<T: GpioHeadMap> DrvRcc for GpioHeadEn<T> {
     fn enable_stop_mode(&self) {
         self.periph.rcc_bussmenr_gpiosmen.set_bit();
     }
+
+    fn clock_id(&self) -> ClockId {
+        T::CLOCK
+    }
+
+    fn set_clock(&self, enabled: bool) {
+        if enabled {
+            self.periph.rcc_busenr_gpioen.set_bit();
+        } else {
+            self.periph.rcc_busenr_gpioen.clear_bit();
+        }
+    }
+
+    fn is_clock_enabled(&self) -> bool {
+        self.periph.rcc_busenr_gpioen.read_bit()
+    }
+}
+
+/// GPIO pin which belongs to a known port head.
+pub trait GpioPinHead: GpioPinMap {
+    /// The port head of the pin.
+    type Head: GpioHeadId;
+
+    /// The pin number within the port.
+    const NUM: u32;
+}
+
+macro_rules! gpio_pin_head {
+    ($head:ident, $($pin:ident: $num:literal),*) => {
+        $(
+            impl GpioPinHead for $pin {
+                type Head = $head;
+
+                const NUM: u32 = $num;
+            }
+        )*
+    };
+}
+
+gpio_pin_head!(
+    GpioAHead, GpioA0: 0, GpioA1: 1, GpioA2: 2, GpioA3: 3, GpioA4: 4, GpioA5: 5, GpioA6: 6,
+    GpioA7: 7, GpioA8: 8, GpioA9: 9, GpioA10: 10, GpioA11: 11, GpioA12: 12, GpioA13: 13,
+    GpioA14: 14, GpioA15: 15
+);
+gpio_pin_head!(
+    GpioBHead, GpioB0: 0, GpioB1: 1, GpioB2: 2, GpioB3: 3, GpioB4: 4, GpioB5: 5, GpioB6: 6,
+    GpioB7: 7, GpioB8: 8, GpioB9: 9, GpioB10: 10, GpioB11: 11, GpioB12: 12, GpioB13: 13,
+    GpioB14: 14, GpioB15: 15
+);
+gpio_pin_head!(
+    GpioCHead, GpioC0: 0, GpioC1: 1, GpioC2: 2, GpioC3: 3, GpioC4: 4, GpioC5: 5, GpioC6: 6,
+    GpioC7: 7, GpioC8: 8, GpioC9: 9, GpioC10: 10, GpioC11: 11, GpioC12: 12, GpioC13: 13,
+    GpioC14: 14, GpioC15: 15
+);
+gpio_pin_head!(
+    GpioDHead, GpioD0: 0, GpioD1: 1, GpioD2: 2, GpioD3: 3, GpioD4: 4, GpioD5: 5, GpioD6: 6,
+    GpioD7: 7, GpioD8: 8, GpioD9: 9, GpioD10: 10, GpioD11: 11, GpioD12: 12, GpioD13: 13,
+    GpioD14: 14, GpioD15: 15
+);
+gpio_pin_head!(
+    GpioEHead, GpioE0: 0, GpioE1: 1, GpioE2: 2, GpioE3: 3, GpioE4: 4, GpioE5: 5, GpioE6: 6,
+    GpioE7: 7, GpioE8: 8, GpioE9: 9, GpioE10: 10, GpioE11: 11, GpioE12: 12, GpioE13: 13,
+    GpioE14: 14, GpioE15: 15
+);
+gpio_pin_head!(
+    GpioFHead, GpioF0: 0, GpioF1: 1, GpioF2: 2, GpioF3: 3, GpioF4: 4, GpioF5: 5, GpioF6: 6,
+    GpioF7: 7, GpioF8: 8, GpioF9: 9, GpioF10: 10, GpioF11: 11, GpioF12: 12, GpioF13: 13,
+    GpioF14: 14, GpioF15: 15
+);
+gpio_pin_head!(
+    GpioGHead, GpioG0: 0, GpioG1: 1, GpioG2: 2, GpioG3: 3, GpioG4: 4, GpioG5: 5, GpioG6: 6,
+    GpioG7: 7, GpioG8: 8, GpioG9: 9, GpioG10: 10, GpioG11: 11, GpioG12: 12, GpioG13: 13,
+    GpioG14: 14, GpioG15: 15
+);
+gpio_pin_head!(
+    GpioHHead, GpioH0: 0, GpioH1: 1, GpioH2: 2, GpioH3: 3, GpioH4: 4, GpioH5: 5, GpioH6: 6,
+    GpioH7: 7, GpioH8: 8, GpioH9: 9, GpioH10: 10, GpioH11: 11, GpioH12: 12, GpioH13: 13,
+    GpioH14: 14, GpioH15: 15
+);
+gpio_pin_head!(
+    GpioIHead, GpioI0: 0, GpioI1: 1, GpioI2: 2, GpioI3: 3, GpioI4: 4, GpioI5: 5, GpioI6: 6,
+    GpioI7: 7, GpioI8: 8, GpioI9: 9, GpioI10: 10, GpioI11: 11
+);
+
+/// GPIO output speed (GPIOx_OSPEEDR).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpioSpeed {
+    /// Low speed.
+    Low = 0b00,
+    /// Medium speed.
+    Medium = 0b01,
+    /// High speed.
+    High = 0b10,
+    /// Very high speed.
+    VeryHigh = 0b11,
+}
+
+/// GPIO pull-up/pull-down (GPIOx_PUPDR).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpioPull {
+    /// No pull-up, pull-down.
+    None = 0b00,
+    /// Pull-up.
+    Up = 0b01,
+    /// Pull-down.
+    Down = 0b10,
+}
+
+/// Pin mode: not configured yet.
+pub struct Unconfigured;
+
+/// Pin mode: input.
+pub struct Input;
+
+/// Pin mode: output of type `T`.
+pub struct Output<T>(PhantomData<T>);
+
+/// Output type: push-pull.
+pub struct PushPull;
+
+/// Output type: open-drain.
+pub struct OpenDrain;
+
+/// Pin mode: alternate function.
+pub struct Alternate;
+
+/// Pin mode: analog.
+pub struct Analog;
+
+/// Pin mode: `M` with the configuration locked until reset, see
+/// [`GpioHeadEn::lock`].
+pub struct Locked<M>(PhantomData<M>);
+
+/// Pin modes which can still be changed.
+pub trait Configurable {}
+
+impl Configurable for Unconfigured {}
+impl Configurable for Input {}
+impl<T> Configurable for Output<T> {}
+impl Configurable for Alternate {}
+impl Configurable for Analog {}
+
+/// Pin modes which drive the pin level.
+pub trait Drive {}
+
+impl<T> Drive for Output<T> {}
+impl<T> Drive for Locked<Output<T>> {}
+
+/// Pin modes which read the pin level.
+pub trait Sense {}
+
+impl Sense for Input {}
+impl Sense for Locked<Input> {}
+
+/// A copyable handle reading the level of an input pin, e.g. from a fiber.
+pub struct GpioProbe<T: GpioPinMap> {
+    gpio_idr_idr: T::CGpioIdrIdr,
+}
+
+/// GPIO pin driver in mode `M`.
+pub struct GpioPin<T: GpioPinMap, M> {
+    periph: GpioPinPeriph<T>,
+    _mode: PhantomData<M>,
+}
+
+impl<T: GpioPinMap> GpioPin<T, Unconfigured> {
+    /// Creates a new [`GpioPin`].
+    #[inline]
+    pub fn new(periph: GpioPinPeriph<T>) -> Self {
+        Self {
+            periph,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<T: GpioPinHead, M> GpioPin<T, M> {
+    /// Returns the pin mask for port-wide operations.
+    #[inline]
+    pub fn mask(&self) -> u16 {
+        1 << T::NUM
+    }
+
+    /// Returns the pull-up/pull-down configuration.
+    pub fn pull(&self) -> GpioPull {
+        match self.periph.gpio_pupdr_pupdr.read_bits() {
+            0b01 => GpioPull::Up,
+            0b10 => GpioPull::Down,
+            _ => GpioPull::None,
+        }
+    }
+
+    fn into_mode<N>(self) -> GpioPin<T, N> {
+        GpioPin {
+            periph: self.periph,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<T: GpioPinHead, M: Configurable> GpioPin<T, M> {
+    /// Releases the peripheral. Locked pins are never released, their
+    /// configuration is frozen until reset.
+    #[inline]
+    pub fn free(self) -> GpioPinPeriph<T> {
+        self.periph
+    }
+
+    /// Configures the pin as a push-pull output.
+    pub fn into_push_pull_output(
+        self,
+        _gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+    ) -> GpioPin<T, Output<PushPull>> {
+        self.periph.gpio_otyper_ot.clear_bit();
+        self.periph.gpio_pupdr_pupdr.write_bits(GpioPull::None as u32);
+        self.periph.gpio_moder_moder.write_bits(0b01);
+        self.into_mode()
+    }
+
+    /// Configures the pin as an open-drain output.
+    pub fn into_open_drain(
+        self,
+        _gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+    ) -> GpioPin<T, Output<OpenDrain>> {
+        self.periph.gpio_otyper_ot.set_bit();
+        self.periph.gpio_pupdr_pupdr.write_bits(GpioPull::None as u32);
+        self.periph.gpio_moder_moder.write_bits(0b01);
+        self.into_mode()
+    }
+
+    /// Configures the pin as an input with `pull`.
+    pub fn into_input(
+        self,
+        _gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+        pull: GpioPull,
+    ) -> GpioPin<T, Input> {
+        self.periph.gpio_pupdr_pupdr.write_bits(pull as u32);
+        self.periph.gpio_moder_moder.write_bits(0b00);
+        self.into_mode()
+    }
+
+    /// Configures the pin for the alternate function number `af`.
+    ///
+    /// See the alternate function mapping table in the datasheet.
+    pub fn into_alternate(
+        self,
+        _gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+        af: u32,
+    ) -> GpioPin<T, Alternate> {
+        assert!(af <= 15, "invalid alternate function");
+        self.periph.gpio_afr_afr.write_bits(af);
+        self.periph.gpio_moder_moder.write_bits(0b10);
+        self.into_mode()
+    }
+
+    /// Connects the pin to the peripheral `signal`.
+    ///
+    /// Only compiles for pins which can carry the signal, see
+    /// [`gpio_af`](crate::drv::gpio_af).
+    pub fn into_signal<S>(
+        self,
+        gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+        _signal: S,
+    ) -> GpioPin<T, Alternate>
+    where
+        T: GpioPinAf<S>,
+    {
+        self.into_alternate(gpio_en, T::AF)
+    }
+
+    /// Configures the pin as an analog input/output.
+    pub fn into_analog(
+        self,
+        _gpio_en: &inventory::Token<GpioHeadEn<T::Head>>,
+    ) -> GpioPin<T, Analog> {
+        self.periph.gpio_pupdr_pupdr.write_bits(GpioPull::None as u32);
+        self.periph.gpio_moder_moder.write_bits(0b11);
+        self.into_mode()
+    }
+}
+
+impl<T: GpioPinHead, O> GpioPin<T, Output<O>> {
+    /// Sets the output speed.
+    #[inline]
+    pub fn set_speed(&self, speed: GpioSpeed) {
+        self.periph.gpio_ospeedr_ospeedr.write_bits(speed as u32);
+    }
+}
+
+impl<T: GpioPinHead, M: Drive> GpioPin<T, M> {
+    /// Drives the pin high.
+    #[inline]
+    pub fn set_high(&self) {
+        self.periph.gpio_bsrr_bs.set_bit();
+    }
+
+    /// Drives the pin low.
+    #[inline]
+    pub fn set_low(&self) {
+        self.periph.gpio_bsrr_br.set_bit();
+    }
+
+    /// Drives the pin to `value`.
+    #[inline]
+    pub fn set(&self, value: bool) {
+        if value {
+            self.set_high();
+        } else {
+            self.set_low();
+        }
+    }
+
+    /// Returns `true` if the pin is driven high.
+    #[inline]
+    pub fn is_set_high(&self) -> bool {
+        self.periph.gpio_odr_odr.read_bit()
+    }
+
+    /// Inverts the pin output.
+    #[inline]
+    pub fn toggle(&self) {
+        self.set(!self.is_set_high());
+    }
+}
+
+impl<T: GpioPinHead, M: Sense> GpioPin<T, M> {
+    /// Returns `true` if the pin level is high.
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.periph.gpio_idr_idr.read_bit()
+    }
+
+    /// Returns `true` if the pin level is low.
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+
+    /// Returns a copyable handle reading the pin level.
+    #[inline]
+    pub fn probe(&self) -> GpioProbe<T> {
+        // The input data bit is read-only, so sharing it is harmless.
+        GpioProbe {
+            gpio_idr_idr: unsafe { T::CGpioIdrIdr::take() },
+        }
+    }
+}
+
+impl<T: GpioPinMap> GpioProbe<T> {
+    /// Returns `true` if the pin level is high.
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.gpio_idr_idr.read_bit()
+    }
+}
+
+impl<T: GpioPinMap> Clone for GpioProbe<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: GpioPinMap> Copy for GpioProbe<T> {}
+
+impl<T: GpioPinHead> GpioPin<T, Alternate> {
+    /// Sets the output speed.
+    #[inline]
+    pub fn set_speed(&self, speed: GpioSpeed) {
+        self.periph.gpio_ospeedr_ospeedr.write_bits(speed as u32);
+    }
+
+    /// Selects an open-drain output stage, e.g. for I2C.
+    #[inline]
+    pub fn set_open_drain(&self, open_drain: bool) {
+        if open_drain {
+            self.periph.gpio_otyper_ot.set_bit();
+        } else {
+            self.periph.gpio_otyper_ot.clear_bit();
+        }
+    }
+
+    /// Selects the pull-up/pull-down.
+    #[inline]
+    pub fn set_pull(&self, pull: GpioPull) {
+        self.periph.gpio_pupdr_pupdr.write_bits(pull as u32);
+    }
 }
@@ -0,0 +1,110 @@
+//! Alternate function mapping of the STM32L496.
+//!
+//! Every peripheral signal is a marker type, and [`GpioPinAf`] is only
+//! implemented for the pins which can carry it. Pass a signal to
+//! [`GpioPin::into_signal`](crate::drv::gpio::GpioPin::into_signal) to
+//! configure a pin:
+//!
+//! ```ignore
+//! let rx = GpioPin::new(periph_gpio_b7!(reg)).into_signal(&gpio_b_en, Usart1Rx);
+//! ```
+//!
+//! Only a selection of the datasheet table (DS11585, Table 17) is listed
+//! here. Add more signals as needed.
+
+use crate::drv::gpio::GpioPinHead;
+use drone_stm32_map::periph::gpio::pin::*;
+
+/// GPIO pin which can carry the peripheral signal `S`.
+pub trait GpioPinAf<S>: GpioPinHead {
+    /// Alternate function number.
+    const AF: u32;
+}
+
+macro_rules! af_table {
+    ($($signal:ident => [$($pin:ident: $af:literal),*];)*) => {
+        $(
+            pub struct $signal;
+
+            $(
+                impl GpioPinAf<$signal> for $pin {
+                    const AF: u32 = $af;
+                }
+            )*
+        )*
+    };
+}
+
+// System.
+af_table! {
+    Mco => [GpioA8: 0];
+}
+
+// USART/UART/LPUART.
+af_table! {
+    Usart1Tx => [GpioA9: 7, GpioB6: 7, GpioG9: 7];
+    Usart1Rx => [GpioA10: 7, GpioB7: 7, GpioG10: 7];
+    Usart2Tx => [GpioA2: 7, GpioD5: 7];
+    Usart2Rx => [GpioA3: 7, GpioA15: 3, GpioD6: 7];
+    Usart3Tx => [GpioB10: 7, GpioC4: 7, GpioC10: 7, GpioD8: 7];
+    Usart3Rx => [GpioB11: 7, GpioC5: 7, GpioC11: 7, GpioD9: 7];
+    Uart4Tx => [GpioA0: 8, GpioC10: 8];
+    Uart4Rx => [GpioA1: 8, GpioC11: 8];
+    Lpuart1Tx => [GpioB11: 8, GpioC1: 8, GpioG7: 8];
+    Lpuart1Rx => [GpioB10: 8, GpioC0: 8, GpioG8: 8];
+}
+
+// SPI.
+af_table! {
+    Spi1Sck => [GpioA5: 5, GpioB3: 5, GpioE13: 5, GpioG2: 5];
+    Spi1Miso => [GpioA6: 5, GpioB4: 5, GpioE14: 5, GpioG3: 5];
+    Spi1Mosi => [GpioA7: 5, GpioB5: 5, GpioE15: 5, GpioG4: 5];
+    Spi2Sck => [GpioB10: 5, GpioB13: 5, GpioD1: 5, GpioI1: 5];
+    Spi2Miso => [GpioB14: 5, GpioC2: 5, GpioD3: 5, GpioI2: 5];
+    Spi2Mosi => [GpioB15: 5, GpioC3: 5, GpioD4: 5, GpioI3: 5];
+    Spi3Sck => [GpioB3: 6, GpioC10: 6, GpioG9: 6];
+    Spi3Miso => [GpioB4: 6, GpioC11: 6, GpioG10: 6];
+    Spi3Mosi => [GpioB5: 6, GpioC12: 6, GpioG11: 6];
+}
+
+// I2C. The pins must be switched to open-drain.
+af_table! {
+    I2c1Scl => [GpioB6: 4, GpioB8: 4, GpioG14: 4];
+    I2c1Sda => [GpioB7: 4, GpioB9: 4, GpioG13: 4];
+    I2c2Scl => [GpioB10: 4, GpioB13: 4, GpioF1: 4, GpioH4: 4];
+    I2c2Sda => [GpioB11: 4, GpioB14: 4, GpioF0: 4, GpioH5: 4];
+    I2c3Scl => [GpioC0: 4, GpioG7: 4, GpioH7: 4];
+    I2c3Sda => [GpioC1: 4, GpioG8: 4, GpioH8: 4];
+}
+
+// Timers.
+af_table! {
+    Tim1Ch1 => [GpioA8: 1, GpioE9: 1];
+    Tim1Ch2 => [GpioA9: 1, GpioE11: 1];
+    Tim1Ch3 => [GpioA10: 1, GpioE13: 1];
+    Tim1Ch4 => [GpioA11: 1, GpioE14: 1];
+    Tim2Ch1 => [GpioA0: 1, GpioA5: 1, GpioA15: 1];
+    Tim2Ch2 => [GpioA1: 1, GpioB3: 1];
+    Tim2Ch3 => [GpioA2: 1, GpioB10: 1];
+    Tim2Ch4 => [GpioA3: 1, GpioB11: 1];
+    Tim3Ch1 => [GpioA6: 2, GpioB4: 2, GpioC6: 2, GpioE3: 2];
+    Tim3Ch2 => [GpioA7: 2, GpioB5: 2, GpioC7: 2, GpioE4: 2];
+    Tim3Ch3 => [GpioB0: 2, GpioC8: 2, GpioE5: 2];
+    Tim3Ch4 => [GpioB1: 2, GpioC9: 2, GpioE6: 2];
+    Tim4Ch1 => [GpioB6: 2, GpioD12: 2];
+    Tim4Ch2 => [GpioB7: 2, GpioD13: 2];
+    Tim4Ch3 => [GpioB8: 2, GpioD14: 2];
+    Tim4Ch4 => [GpioB9: 2, GpioD15: 2];
+    Tim5Ch1 => [GpioA0: 2, GpioF6: 2];
+    Tim5Ch2 => [GpioA1: 2, GpioF7: 2];
+    Tim5Ch3 => [GpioA2: 2, GpioF8: 2];
+    Tim5Ch4 => [GpioA3: 2, GpioF9: 2];
+    Tim8Ch1 => [GpioC6: 3];
+    Tim8Ch2 => [GpioC7: 3];
+    Tim8Ch3 => [GpioC8: 3];
+    Tim8Ch4 => [GpioC9: 3];
+    Tim15Ch1 => [GpioA2: 14, GpioB14: 14, GpioF9: 14, GpioG10: 14];
+    Tim15Ch2 => [GpioA3: 14, GpioB15: 14, GpioF10: 14, GpioG11: 14];
+    Tim16Ch1 => [GpioA6: 14, GpioB8: 14, GpioE0: 14];
+    Tim17Ch1 => [GpioA7: 14, GpioB9: 14, GpioE1: 14];
+}
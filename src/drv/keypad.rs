@@ -0,0 +1,323 @@
+//! Key matrix scanning.
+//!
+//! The rows are open-drain outputs, driven low one at a time during a scan,
+//! so that two keys down in one column never short two rows. The columns are
+//! inputs with pull-ups on EXTI lines triggered on falling edges. While no
+//! key is down all rows are driven low and nothing is scanned: the first key
+//! press pulls its column low and wakes the driver up through EXTI.
+
+use crate::drv::{
+    exti::{ExtiDrv, GpioPinExti},
+    gpio::{GpioHeadEn, GpioHeadId, GpioPin, GpioPinHead, GpioPull, Input, OpenDrain, Output},
+    lptim::LptimDrv,
+};
+use crate::sys::monotonic::{Duration, Instant};
+use alloc::{boxed::Box, vec::Vec};
+use core::{num::NonZeroUsize, pin::Pin, task::Poll};
+use drone_cortexm::thr::prelude::*;
+use drone_stm32_map::periph::exti::{
+    ExtiFtsrFt, ExtiMap, ExtiPrPif, ExtiRtsrRt, ExtiSwierSwi, SyscfgExticrExti,
+};
+use futures::prelude::*;
+use futures::{future, stream};
+
+/// Maximum number of keys, one bit each in the state masks.
+pub const KEYPAD_MAX_KEYS: usize = 64;
+
+/// Column reads before the value is used, to let the lines settle after the
+/// row change.
+const SETTLE_READS: usize = 4;
+
+type WakeStream = Pin<Box<dyn Stream<Item = NonZeroUsize> + Send + Sync>>;
+
+/// Row pins of a keypad on port `R`: an open-drain output, or a tuple of
+/// them in row order.
+pub trait KeypadRows<R: GpioHeadId> {
+    /// Appends the pin masks of the rows to `masks`.
+    fn masks(&self, masks: &mut Vec<u16>);
+}
+
+/// Column pins of a keypad on port `C`: a [`KeypadCol`], or a tuple of them
+/// in column order.
+pub trait KeypadCols<C: GpioHeadId> {
+    /// Appends the pin masks of the columns to `masks`.
+    fn masks(&self, masks: &mut Vec<u16>);
+
+    /// Appends the EXTI streams of the columns to `wakes`.
+    fn wakes(&self, wakes: &mut Vec<WakeStream>);
+}
+
+/// A keypad column: a pulled-up input with the EXTI line of its pin,
+/// triggered on falling edges.
+pub struct KeypadCol<T, ExtiInt>
+where
+    T: GpioPinExti,
+    T::Exti: ExtiMap + SyscfgExticrExti + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+    ExtiInt: IntToken,
+{
+    /// Column input pin.
+    pub pin: GpioPin<T, Input>,
+    /// EXTI line of the pin.
+    pub exti: ExtiDrv<T::Exti, ExtiInt>,
+}
+
+/// Keypad setup.
+pub struct KeypadSetup<'a, R: GpioHeadId, C: GpioHeadId, Rows: KeypadRows<R>, Cols: KeypadCols<C>> {
+    /// Port of the row outputs.
+    pub rows_head: &'a GpioHeadEn<R>,
+    /// Row pins.
+    pub rows: Rows,
+    /// Port of the column inputs.
+    pub cols_head: &'a GpioHeadEn<C>,
+    /// Column pins.
+    pub cols: Cols,
+    /// Scan interval while a key is down.
+    pub scan: Duration,
+    /// Time a key must keep its new state to be reported.
+    pub debounce: Duration,
+}
+
+/// Key state change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Row index.
+    pub row: u8,
+    /// Column index.
+    pub col: u8,
+    /// `true` if the key went down.
+    pub pressed: bool,
+    /// Time of the scan which accepted the change.
+    pub timestamp: Instant,
+}
+
+/// Keypad driver.
+pub struct KeypadDrv<'a, R: GpioHeadId, C: GpioHeadId, Rows: KeypadRows<R>, Cols: KeypadCols<C>> {
+    setup: KeypadSetup<'a, R, C, Rows, Cols>,
+    row_masks: Vec<u16>,
+    col_masks: Vec<u16>,
+    all_rows: u16,
+    all_cols: u16,
+    wakes: Vec<WakeStream>,
+    debounce_scans: u8,
+    stable: u64,
+    changed: u64,
+    counts: [u8; KEYPAD_MAX_KEYS],
+    scanned_at: Instant,
+}
+
+impl<T: GpioPinHead> KeypadRows<T::Head> for GpioPin<T, Output<OpenDrain>> {
+    fn masks(&self, masks: &mut Vec<u16>) {
+        masks.push(self.mask());
+    }
+}
+
+impl<T, ExtiInt> KeypadCols<T::Head> for KeypadCol<T, ExtiInt>
+where
+    T: GpioPinExti,
+    T::Exti: ExtiMap + SyscfgExticrExti + ExtiRtsrRt + ExtiFtsrFt + ExtiSwierSwi + ExtiPrPif,
+    ExtiInt: IntToken,
+{
+    fn masks(&self, masks: &mut Vec<u16>) {
+        assert!(
+            self.pin.pull() == GpioPull::Up,
+            "keypad column not pulled up"
+        );
+        masks.push(self.pin.mask());
+    }
+
+    fn wakes(&self, wakes: &mut Vec<WakeStream>) {
+        wakes.push(Box::pin(self.exti.create_saturating_stream()));
+    }
+}
+
+macro_rules! keypad_lines {
+    ($($line:ident),*) => {
+        impl<R: GpioHeadId, $($line: KeypadRows<R>),*> KeypadRows<R> for ($($line,)*) {
+            #[allow(non_snake_case)]
+            fn masks(&self, masks: &mut Vec<u16>) {
+                let ($($line,)*) = self;
+                $($line.masks(masks);)*
+            }
+        }
+
+        impl<C: GpioHeadId, $($line: KeypadCols<C>),*> KeypadCols<C> for ($($line,)*) {
+            #[allow(non_snake_case)]
+            fn masks(&self, masks: &mut Vec<u16>) {
+                let ($($line,)*) = self;
+                $($line.masks(masks);)*
+            }
+
+            #[allow(non_snake_case)]
+            fn wakes(&self, wakes: &mut Vec<WakeStream>) {
+                let ($($line,)*) = self;
+                $($line.wakes(wakes);)*
+            }
+        }
+    };
+}
+
+keypad_lines!(A, B);
+keypad_lines!(A, B, C);
+keypad_lines!(A, B, C, D);
+keypad_lines!(A, B, C, D, E);
+keypad_lines!(A, B, C, D, E, F);
+keypad_lines!(A, B, C, D, E, F, G);
+keypad_lines!(A, B, C, D, E, F, G, H);
+
+impl<'a, R: GpioHeadId, C: GpioHeadId, Rows: KeypadRows<R>, Cols: KeypadCols<C>>
+    KeypadDrv<'a, R, C, Rows, Cols>
+{
+    /// Sets up a new [`KeypadDrv`] from `setup` values, with all rows driven
+    /// low.
+    pub fn init(setup: KeypadSetup<'a, R, C, Rows, Cols>) -> Self {
+        let mut row_masks = Vec::new();
+        let mut col_masks = Vec::new();
+        let mut wakes = Vec::new();
+        setup.rows.masks(&mut row_masks);
+        setup.cols.masks(&mut col_masks);
+        setup.cols.wakes(&mut wakes);
+        assert!(
+            row_masks.len() * col_masks.len() <= KEYPAD_MAX_KEYS,
+            "too many keys"
+        );
+        assert!(setup.scan > Duration::ZERO, "zero scan interval");
+        let all_rows = row_masks.iter().fold(0, |all, mask| all | mask);
+        let all_cols = col_masks.iter().fold(0, |all, mask| all | mask);
+        let scan = setup.scan.as_micros();
+        let debounce_scans = ((setup.debounce.as_micros() + scan - 1) / scan).max(1);
+        let drv = Self {
+            setup,
+            row_masks,
+            col_masks,
+            all_rows,
+            all_cols,
+            wakes,
+            debounce_scans: debounce_scans.min(u64::from(u8::MAX)) as u8,
+            stable: 0,
+            changed: 0,
+            counts: [0; KEYPAD_MAX_KEYS],
+            scanned_at: Instant::default(),
+        };
+        drv.setup.rows_head.write_masked(0, all_rows);
+        drv
+    }
+
+    /// Releases the underlying resources.
+    pub fn free(self) -> KeypadSetup<'a, R, C, Rows, Cols> {
+        self.setup
+    }
+
+    /// Returns `true` if the key at `row`, `col` is down after debouncing.
+    pub fn is_pressed(&self, row: usize, col: usize) -> bool {
+        assert!(
+            row < self.row_masks.len() && col < self.col_masks.len(),
+            "key out of range"
+        );
+        self.stable & 1 << (row * self.col_masks.len() + col) != 0
+    }
+
+    /// Waits for the next key event.
+    ///
+    /// Scans make the columns toggle too: wakeups from a previous scan only
+    /// cost an extra scan.
+    pub async fn next<LptimInt: IntToken>(&mut self, lptim: &LptimDrv<LptimInt>) -> KeyEvent {
+        loop {
+            if let Some(event) = self.pop_change() {
+                return event;
+            }
+            if self.is_settled() {
+                // Idle: any key press pulls its column low.
+                self.setup.rows_head.write_masked(0, self.all_rows);
+                if self.setup.cols_head.read_all() & self.all_cols == self.all_cols {
+                    self.wake().await;
+                }
+            } else {
+                lptim.sleep(self.setup.scan).await;
+            }
+            self.scan(lptim.now());
+        }
+    }
+
+    /// Converts the driver into a stream of key events.
+    pub fn into_stream<LptimInt: IntToken>(
+        self,
+        lptim: &'a LptimDrv<LptimInt>,
+    ) -> impl Stream<Item = KeyEvent> + 'a
+    where
+        Rows: 'a,
+        Cols: 'a,
+    {
+        stream::unfold(self, move |mut drv| async move {
+            let event = drv.next(lptim).await;
+            Some((event, drv))
+        })
+    }
+
+    fn wake(&mut self) -> impl Future<Output = ()> + '_ {
+        let wakes = &mut self.wakes;
+        future::poll_fn(move |cx| {
+            for wake in wakes.iter_mut() {
+                if let Poll::Ready(pulses) = wake.as_mut().poll_next(cx) {
+                    pulses.expect("keypad wake stream ended");
+                    return Poll::Ready(());
+                }
+            }
+            Poll::Pending
+        })
+    }
+
+    fn scan(&mut self, now: Instant) {
+        let rows = self.setup.rows_head;
+        let cols = self.setup.cols_head;
+        let col_count = self.col_masks.len();
+        for row in 0..self.row_masks.len() {
+            let row_mask = self.row_masks[row];
+            rows.write_masked(self.all_rows & !row_mask, row_mask);
+            let mut levels = 0;
+            for _ in 0..SETTLE_READS {
+                levels = cols.read_all();
+            }
+            for col in 0..col_count {
+                let down = levels & self.col_masks[col] == 0;
+                self.debounce(row * col_count + col, down);
+            }
+        }
+        // Released rows keep the columns quiet until the next scan.
+        rows.write_masked(self.all_rows, 0);
+        self.scanned_at = now;
+    }
+
+    fn debounce(&mut self, key: usize, down: bool) {
+        let bit = 1 << key;
+        if down == (self.stable & bit != 0) {
+            self.counts[key] = 0;
+            return;
+        }
+        self.counts[key] += 1;
+        if self.counts[key] >= self.debounce_scans {
+            self.counts[key] = 0;
+            self.stable ^= bit;
+            self.changed |= bit;
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        self.stable == 0 && self.counts.iter().all(|&count| count == 0)
+    }
+
+    fn pop_change(&mut self) -> Option<KeyEvent> {
+        if self.changed == 0 {
+            return None;
+        }
+        let key = self.changed.trailing_zeros() as usize;
+        let bit = 1 << key;
+        self.changed &= !bit;
+        let col_count = self.col_masks.len();
+        Some(KeyEvent {
+            row: (key / col_count) as u8,
+            col: (key % col_count) as u8,
+            pressed: self.stable & bit != 0,
+            timestamp: self.scanned_at,
+        })
+    }
+}
@@ -0,0 +1,195 @@
+//! PWM brightness control of the user LEDs.
+//!
+//! LD1, LD2 and LD3 are driven by TIM3_CH2, TIM4_CH2 and TIM15_CH1. The pins
+//! must be connected to these signals, see
+//! [`Board::into_pwm`](crate::board::Board::into_pwm).
+
+use crate::clock_gate_set_clock;
+use crate::drv::{
+    clock_gate::{self, ClockId},
+    lptim::LptimDrv,
+};
+use crate::periph::led_pwm::LedPwmPeriph;
+use crate::sys::monotonic::Duration;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+
+/// Number of duty cycle steps. The PWM frequency is the timer clock divided
+/// by this value, e.g. 4 kHz at 4 MHz.
+pub const PWM_STEPS: u32 = 1000;
+
+/// Interval between two brightness updates of a fade.
+const FADE_STEP: Duration = Duration::from_millis(10);
+
+/// Perceived brightness in percent to duty cycle, gamma 2.2. Nonzero
+/// percentages get a duty cycle of at least 1, so a dim LED is still lit.
+const GAMMA: [u16; 101] = [
+    0, 1, 1, 1, 1, 1, 2, 3, 4, 5, 6, 8, 9, 11, 13, 15, 18, 20, 23, 26, 29, 32, 36, 39, 43, 47, 52,
+    56, 61, 66, 71, 76, 82, 87, 93, 99, 106, 112, 119, 126, 133, 141, 148, 156, 164, 173, 181, 190,
+    199, 208, 218, 227, 237, 247, 258, 268, 279, 290, 302, 313, 325, 337, 349, 362, 375, 388, 401,
+    414, 428, 442, 456, 471, 485, 500, 516, 531, 547, 563, 579, 595, 612, 629, 646, 664, 681, 699,
+    718, 736, 755, 774, 793, 813, 832, 852, 873, 893, 914, 935, 957, 978, 1000,
+];
+
+/// PWM channel of an LED.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedPwmChannel {
+    /// TIM3_CH2, LD1.
+    Tim3Ch2,
+    /// TIM4_CH2, LD2.
+    Tim4Ch2,
+    /// TIM15_CH1, LD3.
+    Tim15Ch1,
+}
+
+/// LED PWM driver.
+pub struct LedPwm {
+    periph: LedPwmPeriph,
+}
+
+impl LedPwm {
+    /// Starts the timers with all LEDs off.
+    pub fn init(periph: LedPwmPeriph) -> Self {
+        // TIM3 and TIM4 may also be held by other drivers, e.g. encoders.
+        clock_gate::acquire_with(
+            ClockId::Tim3,
+            || periph.rcc_apb1enr1_tim3en.read_bit(),
+            clock_gate_set_clock!(periph.rcc_apb1enr1_tim3en),
+        );
+        clock_gate::acquire_with(
+            ClockId::Tim4,
+            || periph.rcc_apb1enr1_tim4en.read_bit(),
+            clock_gate_set_clock!(periph.rcc_apb1enr1_tim4en),
+        );
+        clock_gate::acquire_with(
+            ClockId::Tim15,
+            || periph.rcc_apb2enr_tim15en.read_bit(),
+            clock_gate_set_clock!(periph.rcc_apb2enr_tim15en),
+        );
+
+        // PWM mode 1 with preloaded compare registers: the output is high
+        // while CNT < CCRx, so CCRx = PWM_STEPS keeps the LED fully on.
+        periph.tim3_psc.reset();
+        periph.tim3_arr.store(|r| r.write_arr(PWM_STEPS - 1));
+        periph.tim3_ccr2.reset();
+        periph
+            .tim3_ccmr1_output
+            .store(|r| r.write_oc2m(0b110).set_oc2pe());
+        periph.tim3_ccer.store(|r| r.set_cc2e());
+        periph.tim3_egr.store(|r| r.set_ug());
+        periph.tim3_cr1.store(|r| r.set_arpe().set_cen());
+
+        periph.tim4_psc.reset();
+        periph.tim4_arr.store(|r| r.write_arr(PWM_STEPS - 1));
+        periph.tim4_ccr2.reset();
+        periph
+            .tim4_ccmr1_output
+            .store(|r| r.write_oc2m(0b110).set_oc2pe());
+        periph.tim4_ccer.store(|r| r.set_cc2e());
+        periph.tim4_egr.store(|r| r.set_ug());
+        periph.tim4_cr1.store(|r| r.set_arpe().set_cen());
+
+        periph.tim15_psc.reset();
+        periph.tim15_arr.store(|r| r.write_arr(PWM_STEPS - 1));
+        periph.tim15_ccr1.reset();
+        periph
+            .tim15_ccmr1_output
+            .store(|r| r.write_oc1m(0b110).set_oc1pe());
+        periph.tim15_ccer.store(|r| r.set_cc1e());
+        // TIM15 has a break unit: outputs stay off until MOE is set.
+        periph.tim15_bdtr.store(|r| r.set_moe());
+        periph.tim15_egr.store(|r| r.set_ug());
+        periph.tim15_cr1.store(|r| r.set_arpe().set_cen());
+
+        Self { periph }
+    }
+
+    /// Stops the timers and releases the peripheral.
+    pub fn free(self) -> LedPwmPeriph {
+        let periph = self.periph;
+        periph.tim3_cr1.reset();
+        periph.tim4_cr1.reset();
+        periph.tim15_cr1.reset();
+        clock_gate::release_with(
+            ClockId::Tim3,
+            clock_gate_set_clock!(periph.rcc_apb1enr1_tim3en),
+        );
+        clock_gate::release_with(
+            ClockId::Tim4,
+            clock_gate_set_clock!(periph.rcc_apb1enr1_tim4en),
+        );
+        clock_gate::release_with(
+            ClockId::Tim15,
+            clock_gate_set_clock!(periph.rcc_apb2enr_tim15en),
+        );
+        periph
+    }
+
+    /// Sets the raw duty cycle of `channel`, from 0 to [`PWM_STEPS`].
+    pub fn set_duty(&self, channel: LedPwmChannel, duty: u32) {
+        let duty = duty.min(PWM_STEPS);
+        match channel {
+            LedPwmChannel::Tim3Ch2 => self.periph.tim3_ccr2.store(|r| r.write_ccr2(duty)),
+            LedPwmChannel::Tim4Ch2 => self.periph.tim4_ccr2.store(|r| r.write_ccr2(duty)),
+            LedPwmChannel::Tim15Ch1 => self.periph.tim15_ccr1.store(|r| r.write_ccr1(duty)),
+        }
+    }
+
+    /// Returns the raw duty cycle of `channel`.
+    pub fn duty(&self, channel: LedPwmChannel) -> u32 {
+        match channel {
+            LedPwmChannel::Tim3Ch2 => self.periph.tim3_ccr2.load().ccr2(),
+            LedPwmChannel::Tim4Ch2 => self.periph.tim4_ccr2.load().ccr2(),
+            LedPwmChannel::Tim15Ch1 => self.periph.tim15_ccr1.load().ccr1(),
+        }
+    }
+
+    /// Sets the perceived brightness of `channel` in percent.
+    #[inline]
+    pub fn set_brightness(&self, channel: LedPwmChannel, percent: u8) {
+        self.set_duty(channel, GAMMA[usize::from(percent.min(100))].into());
+    }
+
+    /// Returns the perceived brightness of `channel` in percent.
+    pub fn brightness(&self, channel: LedPwmChannel) -> u8 {
+        let duty = self.duty(channel);
+        GAMMA
+            .iter()
+            .position(|&step| u32::from(step) >= duty)
+            .unwrap_or(100) as u8
+    }
+
+    /// Changes the brightness of `channel` to `percent` smoothly over
+    /// `duration`.
+    ///
+    /// Steps are timed by the timer queue, see [`LptimDrv::sleep`].
+    pub async fn fade<LptimInt: IntToken>(
+        &self,
+        lptim: &LptimDrv<LptimInt>,
+        channel: LedPwmChannel,
+        percent: u8,
+        duration: Duration,
+    ) {
+        let from = i32::from(self.brightness(channel));
+        let to = i32::from(percent.min(100));
+        let steps = (duration.as_micros() / FADE_STEP.as_micros()).max(1) as i32;
+        for step in 1..=steps {
+            lptim.sleep(FADE_STEP).await;
+            self.set_brightness(channel, (from + (to - from) * step / steps) as u8);
+        }
+    }
+
+    /// Fades `channel` in and out with a period of `period`.
+    ///
+    /// The returned future never completes.
+    pub async fn breathe<LptimInt: IntToken>(
+        &self,
+        lptim: &LptimDrv<LptimInt>,
+        channel: LedPwmChannel,
+        period: Duration,
+    ) {
+        loop {
+            self.fade(lptim, channel, 100, period / 2).await;
+            self.fade(lptim, channel, 0, period / 2).await;
+        }
+    }
+}
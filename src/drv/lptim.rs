@@ -0,0 +1,295 @@
+//! Low-power timer LPTIM1 clocked from the LSE.
+//!
+//! The timer keeps counting in Stop 2 mode, where the SysTick is halted. It
+//! is the monotonic time base, and its compare match is the alarm of the
+//! software [timer queue](crate::sys::timer).
+
+use crate::drv::{
+    clock_gate::{self, ClockId},
+    common::DrvRcc,
+};
+use crate::periph::lptim::Lptim1Periph;
+use crate::sys::{
+    monotonic::{Duration, Instant, Monotonic},
+    timer::{Alarm, Sleep, Timer},
+};
+use crate::tasks::root::SystemRes;
+use core::sync::atomic::{AtomicU32, Ordering};
+use drone_core::reg::tag::{Crt, Srt};
+use drone_cortexm::{fib, fib::Fiber, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg::{lptim1, rcc};
+
+/// LPTIM1 counter frequency (LSE, no prescaler).
+pub const LPTIM_FREQ: u32 = 32_768;
+
+/// Deadlines closer than this are considered as reached: a compare value
+/// written that late may be missed by the counter.
+const MIN_TICKS: u64 = 3;
+
+/// Number of completed 16 bit counter periods.
+static OVERFLOWS: AtomicU32 = AtomicU32::new(0);
+
+/// LPTIM setup.
+pub struct LptimSetup<LptimInt: IntToken> {
+    /// LPTIM1 peripheral.
+    pub lptim: Lptim1Periph,
+    /// LPTIM1 interrupt.
+    pub lptim_int: LptimInt,
+}
+
+/// A copyable handle to read the LPTIM1 time.
+#[derive(Clone, Copy)]
+pub struct LptimClock {
+    lptim1_isr: lptim1::Isr<Crt>,
+    lptim1_cnt: lptim1::Cnt<Crt>,
+}
+
+/// The LPTIM1 compare match, driving the timer queue.
+#[derive(Clone, Copy)]
+pub struct LptimAlarm {
+    clock: LptimClock,
+    lptim1_icr: lptim1::Icr<Crt>,
+    lptim1_cmp: lptim1::Cmp<Crt>,
+}
+
+/// LPTIM1 driver.
+pub struct LptimDrv<LptimInt: IntToken> {
+    rcc_apb1enr1_lptim1en: rcc::apb1enr1::Lptim1en<Srt>,
+    rcc_apb1rstr1_lptim1rst: rcc::apb1rstr1::Lptim1rst<Srt>,
+    rcc_apb1smenr1_lptim1smen: rcc::apb1smenr1::Lptim1smen<Srt>,
+    clock: LptimClock,
+    lptim1_icr: lptim1::Icr<Crt>,
+    lptim1_cmp: lptim1::Cmp<Crt>,
+    lptim1_cr: lptim1::Cr<Srt>,
+    lptim_int: LptimInt,
+}
+
+impl<LptimInt: IntToken> LptimDrv<LptimInt> {
+    /// Sets up a new [`LptimDrv`] from `setup` values.
+    ///
+    /// The LSE must be enabled beforehand.
+    pub fn init(setup: LptimSetup<LptimInt>, res: &SystemRes) -> Self {
+        let LptimSetup { lptim, lptim_int } = setup;
+        assert!(res.lse.is_ready(), "LSE is not running");
+
+        let drv = Self {
+            rcc_apb1enr1_lptim1en: lptim.rcc_apb1enr1_lptim1en,
+            rcc_apb1rstr1_lptim1rst: lptim.rcc_apb1rstr1_lptim1rst,
+            rcc_apb1smenr1_lptim1smen: lptim.rcc_apb1smenr1_lptim1smen,
+            clock: LptimClock {
+                lptim1_isr: lptim.lptim1_isr.into_copy(),
+                lptim1_cnt: lptim.lptim1_cnt.into_copy(),
+            },
+            lptim1_icr: lptim.lptim1_icr.into_copy(),
+            lptim1_cmp: lptim.lptim1_cmp.into_copy(),
+            lptim1_cr: lptim.lptim1_cr,
+            lptim_int,
+        };
+
+        // Clock the timer from the LSE, also in Sleep and Stop modes.
+        clock_gate::acquire(&drv);
+        drv.enable_stop_mode();
+        lptim.rcc_ccipr_lptim1sel.write_bits(0b11);
+
+        // CFGR and IER may only be written while the timer is disabled.
+        drv.lptim1_cr.reset();
+        lptim.lptim1_cfgr.reset(); // Internal clock, no prescaler.
+        lptim
+            .lptim1_ier
+            .store(|r| r.set_arrmie().set_cmpmie());
+        drv.lptim1_cr.store(|r| r.set_enable());
+        lptim.lptim1_arr.store(|r| r.write_arr(0xFFFF));
+        while !drv.clock.lptim1_isr.load().arrok() {}
+        drv.lptim1_icr.store(|r| r.set_arrokcf());
+
+        drv.lptim_int.add_fib(drv.new_overflow_fib());
+        drv.lptim_int.enable_int();
+        // Start counting in continuous mode.
+        drv.lptim1_cr.store(|r| r.set_enable().set_cntstrt());
+        drv
+    }
+
+    /// Returns a copyable handle to read the time, e.g. from a fiber.
+    #[inline]
+    pub fn clock(&self) -> LptimClock {
+        self.clock
+    }
+
+    /// Returns the current time.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Returns a handle to the timer queue.
+    #[inline]
+    pub fn timer(&self) -> Timer<LptimAlarm> {
+        Timer::new(LptimAlarm {
+            clock: self.clock,
+            lptim1_icr: self.lptim1_icr,
+            lptim1_cmp: self.lptim1_cmp,
+        })
+    }
+
+    /// Waits until `deadline`, see [`Timer::sleep_until`].
+    #[inline]
+    pub fn sleep_until(&self, deadline: Instant) -> Sleep<LptimAlarm> {
+        self.timer().sleep_until(deadline)
+    }
+
+    /// Waits for `duration`, see [`Timer::sleep`].
+    #[inline]
+    pub fn sleep(&self, duration: Duration) -> Sleep<LptimAlarm> {
+        self.timer().sleep(duration)
+    }
+
+    fn new_overflow_fib(&self) -> impl Fiber<Input = (), Yield = (), Return = ()> {
+        let clock = self.clock;
+        let lptim1_icr = self.lptim1_icr;
+        let timer = self.timer();
+        fib::new_fn(move || {
+            let isr = clock.lptim1_isr.load();
+            if isr.arrm() {
+                lptim1_icr.store(|r| r.set_arrmcf());
+                OVERFLOWS.fetch_add(1, Ordering::AcqRel);
+            }
+            if isr.cmpm() {
+                lptim1_icr.store(|r| r.set_cmpmcf());
+            }
+            // Deadlines more than one counter period away are armed on the
+            // overflows.
+            timer.service();
+            fib::Yielded(())
+        })
+    }
+}
+
+impl<LptimInt: IntToken> DrvRcc for LptimDrv<LptimInt> {
+    fn reset(&mut self) {
+        self.rcc_apb1rstr1_lptim1rst.set_bit();
+        self.rcc_apb1rstr1_lptim1rst.clear_bit();
+    }
+
+    fn disable_stop_mode(&self) {
+        self.rcc_apb1smenr1_lptim1smen.clear_bit();
+    }
+
+    fn enable_stop_mode(&self) {
+        self.rcc_apb1smenr1_lptim1smen.set_bit();
+    }
+
+    fn clock_id(&self) -> ClockId {
+        ClockId::Lptim1
+    }
+
+    fn set_clock(&self, enabled: bool) {
+        if enabled {
+            self.rcc_apb1enr1_lptim1en.set_bit();
+        } else {
+            self.rcc_apb1enr1_lptim1en.clear_bit();
+        }
+    }
+
+    fn is_clock_enabled(&self) -> bool {
+        self.rcc_apb1enr1_lptim1en.read_bit()
+    }
+}
+
+impl<LptimInt: IntToken> Monotonic for LptimDrv<LptimInt> {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+impl Monotonic for LptimAlarm {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+impl Alarm for LptimAlarm {
+    fn set(&self, deadline: Instant) -> bool {
+        let deadline = ticks_from_instant(deadline);
+        let now = self.clock.ticks();
+        if deadline <= now + MIN_TICKS {
+            return false;
+        }
+        if deadline - now <= 0xFFFF {
+            // The deadline falls into the current counter period.
+            self.lptim1_icr.store(|r| r.set_cmpokcf());
+            self.lptim1_cmp
+                .store(|r| r.write_cmp(counter_value(deadline)));
+            while !self.clock.lptim1_isr.load().cmpok() {}
+        }
+        true
+    }
+}
+
+impl LptimClock {
+    /// Returns the current time.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        instant_from_ticks(self.ticks())
+    }
+
+    /// Returns the number of ticks since the timer has been started.
+    fn ticks(&self) -> u64 {
+        loop {
+            let overflows = OVERFLOWS.load(Ordering::Acquire);
+            let cnt = self.read_cnt();
+            let pending = self.lptim1_isr.load().arrm();
+            if overflows != OVERFLOWS.load(Ordering::Acquire) {
+                continue;
+            }
+            // ARRM is raised when the counter reaches 0xFFFF, so the period
+            // is counted from that value on.
+            let low = (cnt + 1) & 0xFFFF;
+            // The overflow interrupt may not have been served yet.
+            let high = if pending && low < 0x8000 {
+                overflows.wrapping_add(1)
+            } else {
+                overflows
+            };
+            break u64::from(high) << 16 | u64::from(low);
+        }
+    }
+
+    fn read_cnt(&self) -> u32 {
+        // The counter is clocked asynchronously: two consecutive reads must
+        // return the same value.
+        loop {
+            let first = self.lptim1_cnt.load().cnt();
+            if first == self.lptim1_cnt.load().cnt() {
+                break first;
+            }
+        }
+    }
+}
+
+impl Monotonic for LptimClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        LptimClock::now(self)
+    }
+}
+
+/// Converts ticks to an [`Instant`], rounded down.
+#[inline]
+fn instant_from_ticks(ticks: u64) -> Instant {
+    Instant::from_micros(ticks * 1_000_000 / u64::from(LPTIM_FREQ))
+}
+
+/// Converts an [`Instant`] to ticks, rounded up so that a deadline is never
+/// reached early.
+#[inline]
+fn ticks_from_instant(instant: Instant) -> u64 {
+    (instant.as_micros() * u64::from(LPTIM_FREQ) + 999_999) / 1_000_000
+}
+
+/// Returns the counter value matching the tick count `ticks`.
+#[inline]
+fn counter_value(ticks: u64) -> u32 {
+    ((ticks as u32) & 0xFFFF).wrapping_sub(1) & 0xFFFF
+}
@@ -1,5 +1,6 @@
 //! 32.768 kHz Low Speed External resonator.
 
+use crate::drv::clock_gate;
 use crate::periph::lse::LsePeriph;
 use crate::tasks::root::SystemRes;
 use drone_cortexm::reg::prelude::*;
@@ -23,21 +24,32 @@ impl Lse {
     }
 
     /// Initializes LSE.
+    ///
+    /// Backup domain write access must be enabled beforehand, see
+    /// [`Backup::set_access`](crate::drv::backup::Backup::set_access).
     pub fn init(&self, res: &SystemRes) {
-        res.rcc.set_apb1enr1_pwren();
-        res.rcc.set_pwr_cr1_dbp();
+        // DBP and LPMS are PWR registers.
+        clock_gate::acquire(&res.pwr);
+        assert!(
+            res.backup.is_access_enabled(),
+            "backup domain is write protected"
+        );
         res.rcc.write_pwr_cr1_lpms(0b010);
-        res.rcc.set_pwr_cr4_vbrs();
-        res.rcc.set_pwr_cr4_vbe();
+        clock_gate::release(&res.pwr);
         self.periph.rcc_bdcr_lseon.modify(|r| {
             self.periph.rcc_bdcr_lseon.set(r);
             self.periph.rcc_bdcr_lsebyp.clear(r);
             self.periph.rcc_bdcr_lsedrv.write(r, 0b01);
         });
-        res.rcc.clear_apb1enr1_pwren();
         while !self.periph.rcc_bdcr_lserdy.read_bit_band() {}
     }
 
+    /// Returns `true` if the LSE oscillator is stable.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.periph.rcc_bdcr_lserdy.read_bit_band()
+    }
+
     pub fn reset(&self) {
         self.periph.rcc_bdcr_lseon.modify(|r| {
             self.periph.rcc_bdcr_lseon.clear(r);
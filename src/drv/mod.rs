@@ -1,12 +1,25 @@
 //! Peripheral devices.
 
+pub mod backup;
+pub mod button;
+pub mod clock_gate;
 pub mod common;
+pub mod cycles;
+pub mod encoder;
 pub mod exti;
 pub mod exti_diverged;
+pub mod exti_internal;
 pub mod flash;
 pub mod gpio;
+pub mod gpio_af;
 pub mod hsi16;
+pub mod keypad;
+pub mod led_pwm;
+pub mod lptim;
 pub mod lse;
 pub mod msi;
 pub mod pll;
+pub mod pvd;
+pub mod pwr;
 pub mod rcc;
+pub mod tim32;
@@ -0,0 +1,271 @@
+//! Programmable voltage detector (PVD) and peripheral voltage monitors (PVM).
+
+use crate::drv::clock_gate;
+use crate::periph::pvd::PvdPeriph;
+use crate::tasks::root::SystemRes;
+use drone_core::reg::tag::{Crt, Srt};
+use drone_cortexm::{fib, fib::Fiber, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg::{exti, pwr};
+use futures::prelude::*;
+
+/// Capacity of the supply event ring buffer.
+const STREAM_CAPACITY: usize = 8;
+
+/// PVD threshold (PWR_CR2.PLS).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PvdLevel {
+    /// VPVD0 around 2.0 V.
+    V2_0 = 0b000,
+    /// VPVD1 around 2.2 V.
+    V2_2 = 0b001,
+    /// VPVD2 around 2.4 V.
+    V2_4 = 0b010,
+    /// VPVD3 around 2.5 V.
+    V2_5 = 0b011,
+    /// VPVD4 around 2.6 V.
+    V2_6 = 0b100,
+    /// VPVD5 around 2.8 V.
+    V2_8 = 0b101,
+    /// VPVD6 around 2.9 V.
+    V2_9 = 0b110,
+    /// External input analog voltage PVD_IN (PB7), compared to VREFINT.
+    External = 0b111,
+}
+
+/// Monitored supply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Supply {
+    /// VDD against the PVD threshold.
+    Vdd,
+    /// PVM1: VDDUSB against 1.2 V.
+    Vddusb,
+    /// PVM2: VDDIO2 against 0.9 V.
+    Vddio2,
+    /// PVM3: VDDA against 1.62 V.
+    Vdda1v62,
+    /// PVM4: VDDA against 2.2 V.
+    Vdda2v2,
+}
+
+/// A threshold crossing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SupplyEvent {
+    /// The supply which crossed its threshold.
+    pub supply: Supply,
+    /// `true` if the supply is now below the threshold.
+    pub low: bool,
+}
+
+/// Peripheral voltage monitors to enable.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PvmMonitors {
+    /// PVM1: VDDUSB.
+    pub vddusb: bool,
+    /// PVM2: VDDIO2.
+    pub vddio2: bool,
+    /// PVM3: VDDA against 1.62 V.
+    pub vdda_1v62: bool,
+    /// PVM4: VDDA against 2.2 V.
+    pub vdda_2v2: bool,
+}
+
+/// PVD setup.
+pub struct PvdSetup<PvdInt: IntToken> {
+    /// PVD/PVM peripheral.
+    pub pvd: PvdPeriph,
+    /// PVD_PVM interrupt.
+    pub pvd_int: PvdInt,
+    /// PVD threshold, `None` leaves the PVD disabled.
+    pub level: Option<PvdLevel>,
+    /// Peripheral voltage monitors.
+    pub monitors: PvmMonitors,
+}
+
+/// PVD/PVM driver.
+pub struct PvdDrv<PvdInt: IntToken> {
+    cfg: PvdCfg,
+    flags: PvdFlags,
+    pvd_int: PvdInt,
+}
+
+struct PvdCfg {
+    pwr_cr2_pls: pwr::cr2::Pls<Srt>,
+    pwr_cr2_pvde: pwr::cr2::Pvde<Srt>,
+    pwr_cr2_pvme1: pwr::cr2::Pvme1<Srt>,
+    pwr_cr2_pvme2: pwr::cr2::Pvme2<Srt>,
+    pwr_cr2_pvme3: pwr::cr2::Pvme3<Srt>,
+    pwr_cr2_pvme4: pwr::cr2::Pvme4<Srt>,
+    exti_imr1_im16: exti::imr1::Im16<Srt>,
+    exti_rtsr1_rt16: exti::rtsr1::Rt16<Srt>,
+    exti_ftsr1_ft16: exti::ftsr1::Ft16<Srt>,
+    exti_imr2_im35: exti::imr2::Im35<Srt>,
+    exti_imr2_im36: exti::imr2::Im36<Srt>,
+    exti_imr2_im37: exti::imr2::Im37<Srt>,
+    exti_imr2_im38: exti::imr2::Im38<Srt>,
+    exti_rtsr2_rt35: exti::rtsr2::Rt35<Srt>,
+    exti_rtsr2_rt36: exti::rtsr2::Rt36<Srt>,
+    exti_rtsr2_rt37: exti::rtsr2::Rt37<Srt>,
+    exti_rtsr2_rt38: exti::rtsr2::Rt38<Srt>,
+    exti_ftsr2_ft35: exti::ftsr2::Ft35<Srt>,
+    exti_ftsr2_ft36: exti::ftsr2::Ft36<Srt>,
+    exti_ftsr2_ft37: exti::ftsr2::Ft37<Srt>,
+    exti_ftsr2_ft38: exti::ftsr2::Ft38<Srt>,
+}
+
+#[derive(Clone, Copy)]
+struct PvdFlags {
+    pwr_sr2_pvdo: pwr::sr2::Pvdo<Crt>,
+    pwr_sr2_pvmo1: pwr::sr2::Pvmo1<Crt>,
+    pwr_sr2_pvmo2: pwr::sr2::Pvmo2<Crt>,
+    pwr_sr2_pvmo3: pwr::sr2::Pvmo3<Crt>,
+    pwr_sr2_pvmo4: pwr::sr2::Pvmo4<Crt>,
+    exti_pr1_pif16: exti::pr1::Pif16<Crt>,
+    exti_pr2_pif35: exti::pr2::Pif35<Crt>,
+    exti_pr2_pif36: exti::pr2::Pif36<Crt>,
+    exti_pr2_pif37: exti::pr2::Pif37<Crt>,
+    exti_pr2_pif38: exti::pr2::Pif38<Crt>,
+}
+
+impl<PvdInt: IntToken> PvdDrv<PvdInt> {
+    /// Sets up a new [`PvdDrv`] from `setup` values.
+    ///
+    /// The PWR interface clock must stay enabled for the driver to read the
+    /// comparator outputs from the interrupt.
+    pub fn init(setup: PvdSetup<PvdInt>, res: &SystemRes) -> Self {
+        let PvdSetup {
+            pvd,
+            pvd_int,
+            level,
+            monitors,
+        } = setup;
+        let drv = Self {
+            cfg: PvdCfg {
+                pwr_cr2_pls: pvd.pwr_cr2_pls,
+                pwr_cr2_pvde: pvd.pwr_cr2_pvde,
+                pwr_cr2_pvme1: pvd.pwr_cr2_pvme1,
+                pwr_cr2_pvme2: pvd.pwr_cr2_pvme2,
+                pwr_cr2_pvme3: pvd.pwr_cr2_pvme3,
+                pwr_cr2_pvme4: pvd.pwr_cr2_pvme4,
+                exti_imr1_im16: pvd.exti_imr1_im16,
+                exti_rtsr1_rt16: pvd.exti_rtsr1_rt16,
+                exti_ftsr1_ft16: pvd.exti_ftsr1_ft16,
+                exti_imr2_im35: pvd.exti_imr2_im35,
+                exti_imr2_im36: pvd.exti_imr2_im36,
+                exti_imr2_im37: pvd.exti_imr2_im37,
+                exti_imr2_im38: pvd.exti_imr2_im38,
+                exti_rtsr2_rt35: pvd.exti_rtsr2_rt35,
+                exti_rtsr2_rt36: pvd.exti_rtsr2_rt36,
+                exti_rtsr2_rt37: pvd.exti_rtsr2_rt37,
+                exti_rtsr2_rt38: pvd.exti_rtsr2_rt38,
+                exti_ftsr2_ft35: pvd.exti_ftsr2_ft35,
+                exti_ftsr2_ft36: pvd.exti_ftsr2_ft36,
+                exti_ftsr2_ft37: pvd.exti_ftsr2_ft37,
+                exti_ftsr2_ft38: pvd.exti_ftsr2_ft38,
+            },
+            flags: PvdFlags {
+                pwr_sr2_pvdo: pvd.pwr_sr2_pvdo.into_copy(),
+                pwr_sr2_pvmo1: pvd.pwr_sr2_pvmo1.into_copy(),
+                pwr_sr2_pvmo2: pvd.pwr_sr2_pvmo2.into_copy(),
+                pwr_sr2_pvmo3: pvd.pwr_sr2_pvmo3.into_copy(),
+                pwr_sr2_pvmo4: pvd.pwr_sr2_pvmo4.into_copy(),
+                exti_pr1_pif16: pvd.exti_pr1_pif16.into_copy(),
+                exti_pr2_pif35: pvd.exti_pr2_pif35.into_copy(),
+                exti_pr2_pif36: pvd.exti_pr2_pif36.into_copy(),
+                exti_pr2_pif37: pvd.exti_pr2_pif37.into_copy(),
+                exti_pr2_pif38: pvd.exti_pr2_pif38.into_copy(),
+            },
+            pvd_int,
+        };
+        // Held for good: the comparator outputs are read from the interrupt.
+        clock_gate::acquire(&res.pwr);
+        drv.init_pvd(level);
+        drv.init_pvm(monitors);
+        drv
+    }
+
+    /// Returns `true` if VDD is currently below the PVD threshold.
+    #[inline]
+    pub fn is_vdd_low(&self) -> bool {
+        self.flags.pwr_sr2_pvdo.read_bit()
+    }
+
+    /// Creates a new saturating stream of threshold crossings.
+    pub fn create_saturating_stream(&self) -> impl Stream<Item = SupplyEvent> + Send + Sync {
+        self.pvd_int
+            .add_saturating_stream(STREAM_CAPACITY, self.new_fib())
+    }
+
+    fn new_fib<R>(&self) -> impl Fiber<Input = (), Yield = Option<SupplyEvent>, Return = R> {
+        let flags = self.flags;
+        fib::new_fn(move || {
+            // One event per run: the interrupt fires again while other
+            // pending bits are still set.
+            let event = if flags.exti_pr1_pif16.read_bit() {
+                flags.exti_pr1_pif16.set_bit();
+                Some((Supply::Vdd, flags.pwr_sr2_pvdo.read_bit()))
+            } else if flags.exti_pr2_pif35.read_bit() {
+                flags.exti_pr2_pif35.set_bit();
+                Some((Supply::Vddusb, flags.pwr_sr2_pvmo1.read_bit()))
+            } else if flags.exti_pr2_pif36.read_bit() {
+                flags.exti_pr2_pif36.set_bit();
+                Some((Supply::Vddio2, flags.pwr_sr2_pvmo2.read_bit()))
+            } else if flags.exti_pr2_pif37.read_bit() {
+                flags.exti_pr2_pif37.set_bit();
+                Some((Supply::Vdda1v62, flags.pwr_sr2_pvmo3.read_bit()))
+            } else if flags.exti_pr2_pif38.read_bit() {
+                flags.exti_pr2_pif38.set_bit();
+                Some((Supply::Vdda2v2, flags.pwr_sr2_pvmo4.read_bit()))
+            } else {
+                None
+            };
+            fib::Yielded(event.map(|(supply, low)| SupplyEvent { supply, low }))
+        })
+    }
+
+    fn init_pvd(&self, level: Option<PvdLevel>) {
+        let cfg = &self.cfg;
+        match level {
+            Some(level) => {
+                cfg.pwr_cr2_pls.write_bits(level as u32);
+                cfg.pwr_cr2_pvde.set_bit();
+                // PVDO rises when VDD drops below the threshold and falls
+                // when it recovers: listen to both edges.
+                cfg.exti_rtsr1_rt16.set_bit();
+                cfg.exti_ftsr1_ft16.set_bit();
+                cfg.exti_imr1_im16.set_bit();
+            }
+            None => {
+                cfg.exti_imr1_im16.clear_bit();
+                cfg.pwr_cr2_pvde.clear_bit();
+            }
+        }
+    }
+
+    fn init_pvm(&self, monitors: PvmMonitors) {
+        let cfg = &self.cfg;
+        if monitors.vddusb {
+            cfg.pwr_cr2_pvme1.set_bit();
+            cfg.exti_rtsr2_rt35.set_bit();
+            cfg.exti_ftsr2_ft35.set_bit();
+            cfg.exti_imr2_im35.set_bit();
+        }
+        if monitors.vddio2 {
+            cfg.pwr_cr2_pvme2.set_bit();
+            cfg.exti_rtsr2_rt36.set_bit();
+            cfg.exti_ftsr2_ft36.set_bit();
+            cfg.exti_imr2_im36.set_bit();
+        }
+        if monitors.vdda_1v62 {
+            cfg.pwr_cr2_pvme3.set_bit();
+            cfg.exti_rtsr2_rt37.set_bit();
+            cfg.exti_ftsr2_ft37.set_bit();
+            cfg.exti_imr2_im37.set_bit();
+        }
+        if monitors.vdda_2v2 {
+            cfg.pwr_cr2_pvme4.set_bit();
+            cfg.exti_rtsr2_rt38.set_bit();
+            cfg.exti_ftsr2_ft38.set_bit();
+            cfg.exti_imr2_im38.set_bit();
+        }
+    }
+}
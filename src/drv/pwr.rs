@@ -0,0 +1,296 @@
+//! Power control: Standby mode, wakeup pins and SRAM2 retention.
+
+use crate::consts::SRAM2_BASE;
+use crate::drv::{
+    clock_gate::{self, ClockId},
+    common::DrvRcc,
+};
+use crate::periph::pwr::PwrPeriph;
+use crate::tasks::root::SystemRes;
+use drone_cortexm::{processor, reg::prelude::*};
+
+/// Number of user words kept in SRAM2 across Standby.
+pub const RETAINED_WORDS: usize = 64;
+
+/// Marks the retained SRAM2 block as written before entering Standby.
+const RETAINED_MAGIC: u32 = 0x5342_5932; // "SBY2"
+
+/// Wakeup pins available in Standby mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeupPin {
+    /// WKUP1 on PA0.
+    Wkup1,
+    /// WKUP2 on PC13 (blue user button on the NUCLEO).
+    Wkup2,
+    /// WKUP3 on PE6.
+    Wkup3,
+    /// WKUP4 on PA2.
+    Wkup4,
+    /// WKUP5 on PC5.
+    Wkup5,
+}
+
+/// Wakeup pin polarity (PWR_CR4.WPx).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeupPolarity {
+    /// Wake up on a rising edge (high level).
+    High,
+    /// Wake up on a falling edge (low level).
+    Low,
+}
+
+/// Pull configuration applied to the I/Os while in Standby.
+///
+/// Each entry is a 16 bit mask of pins for ports A..I, written to
+/// PWR_PUCRx and PWR_PDCRx respectively.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandbyPulls {
+    /// Pull-up masks for ports A..I.
+    pub pull_up: [u16; 9],
+    /// Pull-down masks for ports A..I.
+    pub pull_down: [u16; 9],
+}
+
+/// Standby entry configuration.
+pub struct StandbySetup<'a> {
+    /// Enabled wakeup pins with their polarity.
+    pub wakeup_pins: &'a [(WakeupPin, WakeupPolarity)],
+    /// Keep SRAM2 content powered in Standby (PWR_CR3.RRS).
+    pub retain_sram2: bool,
+    /// I/O pull configuration applied in Standby (PWR_CR3.APC).
+    pub pulls: Option<&'a StandbyPulls>,
+}
+
+/// Data block located at the start of SRAM2.
+///
+/// It survives Standby only if [`StandbySetup::retain_sram2`] was set.
+#[repr(C)]
+pub struct Retained {
+    magic: u32,
+    /// User words preserved across Standby.
+    pub words: [u32; RETAINED_WORDS],
+}
+
+/// PWR driver.
+///
+/// Except for [`Pwr::enter_standby`], the methods expect the PWR clock to be
+/// held, see [`clock_gate`].
+pub struct Pwr {
+    periph: PwrPeriph,
+}
+
+impl Pwr {
+    /// Creates a new [`Pwr`].
+    #[inline]
+    pub fn new(periph: PwrPeriph) -> Self {
+        Self { periph }
+    }
+
+    /// Releases the peripheral.
+    #[inline]
+    pub fn free(self) -> PwrPeriph {
+        self.periph
+    }
+
+    /// Returns `true` if the device has been in Standby mode (PWR_SR1.SBF).
+    pub fn woke_from_standby(&self) -> bool {
+        self.periph.pwr_sr1_sbf.read_bit()
+    }
+
+    /// Returns the wakeup pin which caused the last wakeup, if any.
+    pub fn wakeup_pin(&self) -> Option<WakeupPin> {
+        if self.periph.pwr_sr1_wuf1.read_bit() {
+            Some(WakeupPin::Wkup1)
+        } else if self.periph.pwr_sr1_wuf2.read_bit() {
+            Some(WakeupPin::Wkup2)
+        } else if self.periph.pwr_sr1_wuf3.read_bit() {
+            Some(WakeupPin::Wkup3)
+        } else if self.periph.pwr_sr1_wuf4.read_bit() {
+            Some(WakeupPin::Wkup4)
+        } else if self.periph.pwr_sr1_wuf5.read_bit() {
+            Some(WakeupPin::Wkup5)
+        } else {
+            None
+        }
+    }
+
+    /// Clears the Standby and wakeup flags.
+    pub fn clear_standby_flags(&self) {
+        self.periph.pwr_scr_csbf.set_bit();
+        self.clear_wakeup_flags();
+    }
+
+    /// Returns the data block kept in SRAM2.
+    ///
+    /// # Safety
+    ///
+    /// SRAM2 must be excluded from the linker memory map (see `Drone.toml`)
+    /// and the returned reference must not be aliased.
+    pub unsafe fn retained(&self) -> &'static mut Retained {
+        &mut *(SRAM2_BASE as *mut Retained)
+    }
+
+    /// Selects deep sleep (SCB_SCR.SLEEPDEEP) for the next WFI/WFE.
+    ///
+    /// The actual mode is selected by PWR_CR1.LPMS.
+    #[inline]
+    pub fn set_deep_sleep(&self, enabled: bool) {
+        if enabled {
+            self.periph.scb_scr_sleepdeep.set_bit();
+        } else {
+            self.periph.scb_scr_sleepdeep.clear_bit();
+        }
+    }
+
+    /// Enters Standby mode. The device restarts from reset on wakeup.
+    pub fn enter_standby(&self, res: &SystemRes, setup: &StandbySetup<'_>) -> ! {
+        // Never released: the device restarts from reset.
+        clock_gate::acquire(self);
+
+        // The wakeup pins must be disabled while the polarity is changed,
+        // otherwise a spurious wakeup flag may be set.
+        self.periph.pwr_cr3_ewup1.clear_bit();
+        self.periph.pwr_cr3_ewup2.clear_bit();
+        self.periph.pwr_cr3_ewup3.clear_bit();
+        self.periph.pwr_cr3_ewup4.clear_bit();
+        self.periph.pwr_cr3_ewup5.clear_bit();
+        for &(pin, polarity) in setup.wakeup_pins {
+            self.set_polarity(pin, polarity);
+        }
+        self.clear_wakeup_flags();
+        self.periph.pwr_scr_csbf.set_bit();
+        for &(pin, _) in setup.wakeup_pins {
+            self.enable_wakeup_pin(pin);
+        }
+
+        if setup.retain_sram2 {
+            unsafe { self.retained() }.seal();
+            self.periph.pwr_cr3_rrs.set_bit();
+        } else {
+            self.periph.pwr_cr3_rrs.clear_bit();
+        }
+
+        match setup.pulls {
+            Some(pulls) => {
+                self.write_pulls(pulls);
+                self.periph.pwr_cr3_apc.set_bit();
+            }
+            None => self.periph.pwr_cr3_apc.clear_bit(),
+        }
+
+        // 011: Standby mode.
+        res.rcc.write_pwr_cr1_lpms(0b011);
+        self.periph.scb_scr_sleepdeep.set_bit();
+        loop {
+            processor::wait_for_int();
+        }
+    }
+
+    fn clear_wakeup_flags(&self) {
+        self.periph.pwr_scr_cwuf1.set_bit();
+        self.periph.pwr_scr_cwuf2.set_bit();
+        self.periph.pwr_scr_cwuf3.set_bit();
+        self.periph.pwr_scr_cwuf4.set_bit();
+        self.periph.pwr_scr_cwuf5.set_bit();
+    }
+
+    fn set_polarity(&self, pin: WakeupPin, polarity: WakeupPolarity) {
+        let low = polarity == WakeupPolarity::Low;
+        match (pin, low) {
+            (WakeupPin::Wkup1, true) => self.periph.pwr_cr4_wp1.set_bit(),
+            (WakeupPin::Wkup1, false) => self.periph.pwr_cr4_wp1.clear_bit(),
+            (WakeupPin::Wkup2, true) => self.periph.pwr_cr4_wp2.set_bit(),
+            (WakeupPin::Wkup2, false) => self.periph.pwr_cr4_wp2.clear_bit(),
+            (WakeupPin::Wkup3, true) => self.periph.pwr_cr4_wp3.set_bit(),
+            (WakeupPin::Wkup3, false) => self.periph.pwr_cr4_wp3.clear_bit(),
+            (WakeupPin::Wkup4, true) => self.periph.pwr_cr4_wp4.set_bit(),
+            (WakeupPin::Wkup4, false) => self.periph.pwr_cr4_wp4.clear_bit(),
+            (WakeupPin::Wkup5, true) => self.periph.pwr_cr4_wp5.set_bit(),
+            (WakeupPin::Wkup5, false) => self.periph.pwr_cr4_wp5.clear_bit(),
+        }
+    }
+
+    fn enable_wakeup_pin(&self, pin: WakeupPin) {
+        match pin {
+            WakeupPin::Wkup1 => self.periph.pwr_cr3_ewup1.set_bit(),
+            WakeupPin::Wkup2 => self.periph.pwr_cr3_ewup2.set_bit(),
+            WakeupPin::Wkup3 => self.periph.pwr_cr3_ewup3.set_bit(),
+            WakeupPin::Wkup4 => self.periph.pwr_cr3_ewup4.set_bit(),
+            WakeupPin::Wkup5 => self.periph.pwr_cr3_ewup5.set_bit(),
+        }
+    }
+
+    fn write_pulls(&self, pulls: &StandbyPulls) {
+        let up = &pulls.pull_up;
+        let down = &pulls.pull_down;
+        self.periph.pwr_pucra.store_bits(up[0].into());
+        self.periph.pwr_pucrb.store_bits(up[1].into());
+        self.periph.pwr_pucrc.store_bits(up[2].into());
+        self.periph.pwr_pucrd.store_bits(up[3].into());
+        self.periph.pwr_pucre.store_bits(up[4].into());
+        self.periph.pwr_pucrf.store_bits(up[5].into());
+        self.periph.pwr_pucrg.store_bits(up[6].into());
+        self.periph.pwr_pucrh.store_bits(up[7].into());
+        self.periph.pwr_pucri.store_bits(up[8].into());
+        self.periph.pwr_pdcra.store_bits(down[0].into());
+        self.periph.pwr_pdcrb.store_bits(down[1].into());
+        self.periph.pwr_pdcrc.store_bits(down[2].into());
+        self.periph.pwr_pdcrd.store_bits(down[3].into());
+        self.periph.pwr_pdcre.store_bits(down[4].into());
+        self.periph.pwr_pdcrf.store_bits(down[5].into());
+        self.periph.pwr_pdcrg.store_bits(down[6].into());
+        self.periph.pwr_pdcrh.store_bits(down[7].into());
+        self.periph.pwr_pdcri.store_bits(down[8].into());
+    }
+}
+
+impl DrvRcc for Pwr {
+    fn reset(&mut self) {
+        self.periph.rcc_apb1rstr1_pwrrst.set_bit();
+        self.periph.rcc_apb1rstr1_pwrrst.clear_bit();
+    }
+
+    fn disable_stop_mode(&self) {
+        self.periph.rcc_apb1smenr1_pwrsmen.clear_bit();
+    }
+
+    fn enable_stop_mode(&self) {
+        self.periph.rcc_apb1smenr1_pwrsmen.set_bit();
+    }
+
+    fn clock_id(&self) -> ClockId {
+        ClockId::Pwr
+    }
+
+    fn set_clock(&self, enabled: bool) {
+        if enabled {
+            self.periph.rcc_apb1enr1_pwren.set_bit();
+        } else {
+            self.periph.rcc_apb1enr1_pwren.clear_bit();
+        }
+    }
+
+    fn is_clock_enabled(&self) -> bool {
+        self.periph.rcc_apb1enr1_pwren.read_bit()
+    }
+}
+
+impl Retained {
+    /// Returns `true` if the block was written before the last Standby entry.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.magic == RETAINED_MAGIC
+    }
+
+    /// Marks the block as valid.
+    #[inline]
+    pub fn seal(&mut self) {
+        self.magic = RETAINED_MAGIC;
+    }
+
+    /// Invalidates the block, e.g. after it has been consumed.
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.magic = 0;
+    }
+}
@@ -4,6 +4,27 @@ use crate::periph::rcc::RccPeriph;
 use crate::tasks::root::SystemRes;
 use drone_cortexm::reg::prelude::*;
 
+/// Reset flags read from RCC_CSR.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetFlags {
+    /// Low-power reset flag (LPWRSTF).
+    pub low_power: bool,
+    /// Window watchdog reset flag (WWDGRSTF).
+    pub window_watchdog: bool,
+    /// Independent watchdog reset flag (IWDGRSTF).
+    pub independent_watchdog: bool,
+    /// Software reset flag (SFTRSTF).
+    pub software: bool,
+    /// BOR flag (BORRSTF).
+    pub brown_out: bool,
+    /// Pin reset flag (PINRSTF).
+    pub pin: bool,
+    /// Option byte loader reset flag (OBLRSTF).
+    pub option_byte: bool,
+    /// Firewall reset flag (FWRSTF).
+    pub firewall: bool,
+}
+
 /// RCC driver.
 pub struct Rcc {
     periph: RccPeriph,
@@ -35,7 +56,6 @@ impl Rcc {
         self.periph
             .rcc_cfgr
             .store(|r| r.write_sw(0b00).write_ppre1(0b110));
-        self.periph.rcc_apb1enr1.reset();
     }
 
     /// Read the system clock switch status from mcu.
@@ -43,22 +63,29 @@ impl Rcc {
         self.periph.rcc_cfgr.sws.read_bits() as u32
     }
 
-    /// Power interface clock enable.
-    #[inline]
-    pub fn set_apb1enr1_pwren(&self) -> () {
-        self.periph.rcc_apb1enr1.modify(|r| r.set_pwren());
+    /// Reads the APB1 prescaler (PPRE1).
+    pub fn read_ppre1(&self) -> u32 {
+        self.periph.rcc_cfgr.ppre1.read_bits() as u32
     }
 
-    /// Power interface clock disable.
-    #[inline]
-    pub fn clear_apb1enr1_pwren(&self) -> () {
-        self.periph.rcc_apb1enr1.modify(|r| r.clear_pwren());
+    /// Reads the reset flags from RCC_CSR.
+    pub fn read_reset_flags(&self) -> ResetFlags {
+        let csr = self.periph.rcc_csr.load();
+        ResetFlags {
+            low_power: csr.lpwrstf(),
+            window_watchdog: csr.wwdgrstf(),
+            independent_watchdog: csr.iwdgrstf(),
+            software: csr.sftrstf(),
+            brown_out: csr.borrstf(),
+            pin: csr.pinrstf(),
+            option_byte: csr.oblrstf(),
+            firewall: csr.fwrstf(),
+        }
     }
 
-    /// Disable backup domain write protection
-    #[inline]
-    pub fn set_pwr_cr1_dbp(&self) {
-        self.periph.pwr_cr1_dbp.set_bit();
+    /// Clears the reset flags (RMVF).
+    pub fn clear_reset_flags(&self) {
+        self.periph.rcc_csr.modify(|r| r.set_rmvf());
     }
 
     /// Low-power mode selection.
@@ -66,16 +93,4 @@ impl Rcc {
     pub fn write_pwr_cr1_lpms(&self, lpms: u32) -> () {
         self.periph.pwr_cr1_lpms.write_bits(lpms);
     }
-
-    /// V-BAT battery charging resistor selection
-    #[inline]
-    pub fn set_pwr_cr4_vbrs(&self) -> () {
-        self.periph.pwr_cr4_vbrs.set_bit();
-    }
-
-    /// V-BAT battery charging enable
-    #[inline]
-    pub fn set_pwr_cr4_vbe(&self) -> () {
-        self.periph.pwr_cr4_vbe.set_bit();
-    }
 }
@@ -0,0 +1,301 @@
+//! 32 bit general-purpose timers TIM2 and TIM5.
+//!
+//! A timer runs one mode at a time: a one-shot deadline, a periodic stream or
+//! a free-running counter. Starting a mode replaces the previous one: its
+//! fibers are tagged with a generation, and the vector is pended so that the
+//! fibers of older generations end without touching the status register. The
+//! prescaler and auto-reload values are computed from the APB1 timer clock
+//! at the time the mode is started, see [`System::apb1_tim_clk`].
+
+use crate::{
+    clock_gate_set_clock,
+    drv::clock_gate::{self, ClockId},
+    periph::tim32::{Tim32Tim2Periph, Tim32Tim5Periph},
+    sys::{monotonic::Duration, system::System},
+    thr,
+};
+use core::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use drone_core::reg::tag::Crt;
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg::{tim2, tim5};
+use futures::prelude::*;
+
+/// A 32 bit timer peripheral.
+pub trait Tim32: Send + 'static {
+    /// Returns the generation of the current mode.
+    fn generation() -> &'static AtomicU32;
+
+    /// Acquires the timer clock.
+    fn enable(&self);
+
+    /// Stops the timer and releases its clock.
+    fn disable(&self);
+
+    /// Stops the timer and masks its interrupt.
+    fn stop(&self);
+
+    /// Starts the timer with the given prescaler and auto-reload values.
+    /// `one_pulse` stops it at the first update, `interrupt` enables the
+    /// update interrupt.
+    fn start(&self, psc: u32, arr: u32, one_pulse: bool, interrupt: bool);
+
+    /// Returns the counter value.
+    fn counter(&self) -> u32;
+
+    /// Clears the update flag and returns whether it was set. Only called
+    /// from the update interrupt.
+    fn take_update() -> bool;
+}
+
+/// Marker for the interrupt of a 32 bit timer.
+pub trait Tim32Vector<TimInt: IntToken> {}
+
+macro_rules! tim32 {
+    (
+        $periph:ident,
+        $tim:ident,
+        $int:ident,
+        $clock:ident,
+        $timen:ident,
+        $cr1:ident,
+        $dier:ident,
+        $sr:ident,
+        $egr:ident,
+        $cnt:ident,
+        $psc:ident,
+        $arr:ident,
+    ) => {
+        impl Tim32 for $periph {
+            fn generation() -> &'static AtomicU32 {
+                static GENERATION: AtomicU32 = AtomicU32::new(0);
+                &GENERATION
+            }
+
+            fn enable(&self) {
+                clock_gate::acquire_with(
+                    ClockId::$clock,
+                    || self.$timen.read_bit(),
+                    clock_gate_set_clock!(self.$timen),
+                );
+            }
+
+            fn disable(&self) {
+                self.stop();
+                clock_gate::release_with(ClockId::$clock, clock_gate_set_clock!(self.$timen));
+            }
+
+            fn stop(&self) {
+                self.$cr1.reset();
+                self.$dier.reset();
+                self.$sr.reset();
+            }
+
+            fn start(&self, psc: u32, arr: u32, one_pulse: bool, interrupt: bool) {
+                self.stop();
+                self.$psc.store_bits(psc);
+                self.$arr.store_bits(arr);
+                // URS: the update generation below loads the prescaler
+                // without raising the update flag.
+                self.$cr1.store(|r| r.set_urs());
+                self.$egr.store(|r| r.set_ug());
+                self.$sr.reset();
+                if interrupt {
+                    self.$dier.store(|r| r.set_uie());
+                }
+                self.$cr1.store(|r| {
+                    if one_pulse {
+                        r.set_opm();
+                    }
+                    r.set_urs().set_cen()
+                });
+            }
+
+            #[inline]
+            fn counter(&self) -> u32 {
+                self.$cnt.load_bits()
+            }
+
+            fn take_update() -> bool {
+                let sr = unsafe { $tim::Sr::<Crt>::take() };
+                if sr.load().uif() {
+                    sr.modify(|r| r.clear_uif());
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        impl Tim32Vector<thr::$int> for $periph {}
+    };
+}
+
+tim32!(
+    Tim32Tim2Periph,
+    tim2,
+    Tim2,
+    Tim2,
+    rcc_apb1enr1_tim2en,
+    tim2_cr1,
+    tim2_dier,
+    tim2_sr,
+    tim2_egr,
+    tim2_cnt,
+    tim2_psc,
+    tim2_arr,
+);
+tim32!(
+    Tim32Tim5Periph,
+    tim5,
+    Tim5,
+    Tim5,
+    rcc_apb1enr1_tim5en,
+    tim5_cr1,
+    tim5_dier,
+    tim5_sr,
+    tim5_egr,
+    tim5_cnt,
+    tim5_psc,
+    tim5_arr,
+);
+
+/// 32 bit timer setup.
+pub struct Tim32Setup<Tim: Tim32, TimInt: IntToken> {
+    /// Timer peripheral, e.g. from `periph_tim32_tim2!`.
+    pub tim: Tim,
+    /// Timer interrupt. The caller enables it in the NVIC, e.g. with
+    /// `thr.tim_2.enable_int()`.
+    pub tim_int: TimInt,
+}
+
+/// The timer mode of an [`after`](Tim32Drv::after) future has been replaced
+/// before its deadline.
+#[derive(Debug)]
+pub struct Tim32Superseded;
+
+/// 32 bit timer driver.
+pub struct Tim32Drv<Tim: Tim32, TimInt: IntToken> {
+    tim: Tim,
+    tim_int: TimInt,
+}
+
+impl<Tim: Tim32, TimInt: IntToken> Tim32Drv<Tim, TimInt> {
+    /// Sets up a new [`Tim32Drv`] from `setup` values, with the timer
+    /// stopped.
+    pub fn init(setup: Tim32Setup<Tim, TimInt>) -> Self
+    where
+        Tim: Tim32Vector<TimInt>,
+    {
+        let Tim32Setup { tim, tim_int } = setup;
+        tim.enable();
+        tim.stop();
+        Self { tim, tim_int }
+    }
+
+    /// Stops the timer and releases the underlying resources.
+    pub fn free(self) -> Tim32Setup<Tim, TimInt> {
+        self.retire();
+        self.tim.disable();
+        Tim32Setup {
+            tim: self.tim,
+            tim_int: self.tim_int,
+        }
+    }
+
+    /// Stops the timer, replacing the current mode.
+    #[inline]
+    pub fn stop(&self) {
+        self.retire();
+        self.tim.stop();
+    }
+
+    /// Returns a future completing once after `duration`.
+    ///
+    /// Resolves to [`Tim32Superseded`] if another mode is started or the
+    /// timer is stopped before.
+    pub fn after(
+        &self,
+        duration: Duration,
+    ) -> impl Future<Output = Result<(), Tim32Superseded>> + Send + Sync {
+        let (psc, arr) = timing(System::apb1_tim_clk(), duration);
+        let generation = self.retire();
+        let future = self.tim_int.add_future(fib::new_fn(move || {
+            if Tim::generation().load(Ordering::Acquire) != generation {
+                fib::Complete(Err(Tim32Superseded))
+            } else if Tim::take_update() {
+                fib::Complete(Ok(()))
+            } else {
+                fib::Yielded(())
+            }
+        }));
+        self.tim.start(psc, arr, true, true);
+        future
+    }
+
+    /// Returns a stream yielding every `period`. The items are the numbers
+    /// of periods elapsed since the previous one.
+    ///
+    /// The stream ends once another mode is started or the timer is stopped.
+    pub fn every(&self, period: Duration) -> impl Stream<Item = NonZeroUsize> + Send + Sync {
+        let (psc, arr) = timing(System::apb1_tim_clk(), period);
+        let generation = self.retire();
+        let stream = self
+            .tim_int
+            .add_saturating_pulse_stream(fib::new_fn(move || {
+                if Tim::generation().load(Ordering::Acquire) != generation {
+                    fib::Complete(None)
+                } else if Tim::take_update() {
+                    fib::Yielded(Some(1))
+                } else {
+                    fib::Yielded(None)
+                }
+            }));
+        self.tim.start(psc, arr, false, true);
+        stream
+    }
+
+    /// Starts the counter running freely at `freq` Hz, wrapping after 2^32
+    /// counts.
+    pub fn start_counter(&self, freq: u32) {
+        let psc = (System::apb1_tim_clk() / freq.max(1)).max(1) - 1;
+        assert!(psc <= 0xFFFF, "counter frequency too low");
+        self.retire();
+        self.tim.start(psc, u32::MAX, false, false);
+    }
+
+    /// Returns the counter value.
+    #[inline]
+    pub fn counter(&self) -> u32 {
+        self.tim.counter()
+    }
+
+    /// Starts a new generation of fibers and returns it. The vector is
+    /// pended, the fibers of older generations end even if the timer raises
+    /// no more interrupts.
+    fn retire(&self) -> u32 {
+        let generation = Tim::generation()
+            .fetch_add(1, Ordering::AcqRel)
+            .wrapping_add(1);
+        self.tim_int.trigger();
+        generation
+    }
+}
+
+/// Returns the prescaler and auto-reload values for an update every
+/// `duration` at `clock` Hz.
+fn timing(clock: u32, duration: Duration) -> (u32, u32) {
+    let ticks = duration
+        .as_micros()
+        .checked_mul(u64::from(clock))
+        .expect("timer duration too long")
+        / 1_000_000;
+    let ticks = ticks.max(1);
+    // The smallest prescaler keeps the best resolution.
+    let psc = (ticks - 1) >> 32;
+    assert!(psc <= 0xFFFF, "timer duration too long");
+    let arr = ticks / (psc + 1) - 1;
+    (psc as u32, arr as u32)
+}
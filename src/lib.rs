@@ -8,6 +8,8 @@
 
 extern crate alloc;
 
+#[macro_use]
+pub mod board;
 #[macro_use]
 pub mod drv;
 #[macro_use]
@@ -33,7 +35,6 @@ stm32_reg_tokens! {
     index => pub Regs;
 
     exclude => {
-        dwt_cyccnt,
         itm_tpr, itm_tcr, itm_lar,
         tpiu_acpr, tpiu_sppr, tpiu_ffcr,
 
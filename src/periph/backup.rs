@@ -0,0 +1,30 @@
+//! Backup domain control.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts backup domain register tokens.
+    pub macro periph_backup;
+
+    /// Backup domain peripheral.
+    pub struct BackupPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::backup;
+
+    RCC {
+        BDCR {
+            BDRST;
+        }
+    }
+
+    PWR {
+        CR1 {
+            DBP;
+        }
+        CR4 {
+            VBRS;
+            VBE;
+        }
+    }
+}
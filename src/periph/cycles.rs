@@ -0,0 +1,27 @@
+//! DWT cycle counter.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts the cycle counter register tokens.
+    pub macro periph_cycles;
+
+    /// Cycle counter peripheral.
+    pub struct CyclesPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::cycles;
+
+    SCB {
+        DEMCR {
+            TRCENA;
+        }
+    }
+
+    DWT {
+        CTRL {
+            CYCCNTENA;
+        }
+        CYCCNT;
+    }
+}
@@ -0,0 +1,148 @@
+//! Timers in encoder interface mode.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts the TIM2 encoder register tokens.
+    pub macro periph_encoder_tim2;
+
+    /// TIM2 (32 bit): CH1 and CH2 on PA0/PA5/PA15 and PA1/PB3.
+    pub struct EncoderTim2Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::encoder;
+
+    RCC {
+        APB1ENR1 {
+            TIM2EN;
+        }
+    }
+
+    TIM2 {
+        CR1;
+        DIER;
+        SR;
+        SMCR;
+        CCMR1_Input;
+        CCER;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
+
+periph::singular! {
+    /// Extracts the TIM3 encoder register tokens.
+    pub macro periph_encoder_tim3;
+
+    /// TIM3: CH1 and CH2 on PA6/PB4/PC6/PE3 and PA7/PB5/PC7/PE4.
+    pub struct EncoderTim3Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::encoder;
+
+    RCC {
+        APB1ENR1 {
+            TIM3EN;
+        }
+    }
+
+    TIM3 {
+        CR1;
+        DIER;
+        SR;
+        SMCR;
+        CCMR1_Input;
+        CCER;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
+
+periph::singular! {
+    /// Extracts the TIM4 encoder register tokens.
+    pub macro periph_encoder_tim4;
+
+    /// TIM4: CH1 and CH2 on PB6/PD12 and PB7/PD13.
+    pub struct EncoderTim4Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::encoder;
+
+    RCC {
+        APB1ENR1 {
+            TIM4EN;
+        }
+    }
+
+    TIM4 {
+        CR1;
+        DIER;
+        SR;
+        SMCR;
+        CCMR1_Input;
+        CCER;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
+
+periph::singular! {
+    /// Extracts the TIM5 encoder register tokens.
+    pub macro periph_encoder_tim5;
+
+    /// TIM5 (32 bit): CH1 and CH2 on PA0/PF6 and PA1/PF7.
+    pub struct EncoderTim5Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::encoder;
+
+    RCC {
+        APB1ENR1 {
+            TIM5EN;
+        }
+    }
+
+    TIM5 {
+        CR1;
+        DIER;
+        SR;
+        SMCR;
+        CCMR1_Input;
+        CCER;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
+
+periph::singular! {
+    /// Extracts the TIM8 encoder register tokens.
+    pub macro periph_encoder_tim8;
+
+    /// TIM8: CH1 and CH2 on PC6 and PC7.
+    pub struct EncoderTim8Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::encoder;
+
+    RCC {
+        APB2ENR {
+            TIM8EN;
+        }
+    }
+
+    TIM8 {
+        CR1;
+        DIER;
+        SR;
+        SMCR;
+        CCMR1_Input;
+        CCER;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
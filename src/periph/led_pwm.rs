@@ -0,0 +1,58 @@
+//! Timers driving the user LEDs.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts the LED timers register tokens.
+    pub macro periph_led_pwm;
+
+    /// LED timers peripheral.
+    pub struct LedPwmPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::led_pwm;
+
+    RCC {
+        APB1ENR1 {
+            TIM3EN;
+            TIM4EN;
+        }
+        APB2ENR {
+            TIM15EN;
+        }
+    }
+
+    // LD1 on PC7, channel 2.
+    TIM3 {
+        CR1;
+        EGR;
+        CCMR1_Output;
+        CCER;
+        PSC;
+        ARR;
+        CCR2;
+    }
+
+    // LD2 on PB7, channel 2.
+    TIM4 {
+        CR1;
+        EGR;
+        CCMR1_Output;
+        CCER;
+        PSC;
+        ARR;
+        CCR2;
+    }
+
+    // LD3 on PB14, channel 1.
+    TIM15 {
+        CR1;
+        EGR;
+        CCMR1_Output;
+        CCER;
+        BDTR;
+        PSC;
+        ARR;
+        CCR1;
+    }
+}
@@ -0,0 +1,40 @@
+//! Low-power timer.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts LPTIM1 register tokens.
+    pub macro periph_lptim1;
+
+    /// LPTIM1 peripheral.
+    pub struct Lptim1Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::lptim;
+
+    RCC {
+        APB1ENR1 {
+            LPTIM1EN;
+        }
+        APB1RSTR1 {
+            LPTIM1RST;
+        }
+        APB1SMENR1 {
+            LPTIM1SMEN;
+        }
+        CCIPR {
+            LPTIM1SEL;
+        }
+    }
+
+    LPTIM1 {
+        ISR;
+        ICR;
+        IER;
+        CFGR;
+        CR;
+        CMP;
+        ARR;
+        CNT;
+    }
+}
@@ -1,8 +1,18 @@
 //! Peripherals.
 
+#[macro_use]
+pub mod backup;
+#[macro_use]
+pub mod cycles;
+#[macro_use]
+pub mod encoder;
 #[macro_use]
 pub mod flash;
 #[macro_use]
+pub mod led_pwm;
+#[macro_use]
+pub mod lptim;
+#[macro_use]
 pub mod lse;
 #[macro_use]
 pub mod msi;
@@ -11,4 +21,12 @@ pub mod hsi16;
 #[macro_use]
 pub mod pll;
 #[macro_use]
+pub mod pvd;
+#[macro_use]
+pub mod pwr;
+#[macro_use]
 pub mod rcc;
+#[macro_use]
+pub mod syscfg;
+#[macro_use]
+pub mod tim32;
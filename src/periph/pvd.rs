@@ -0,0 +1,71 @@
+//! Programmable voltage detector and peripheral voltage monitoring.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts PVD/PVM register tokens.
+    pub macro periph_pvd;
+
+    /// PVD/PVM peripheral.
+    pub struct PvdPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::pvd;
+
+    PWR {
+        CR2 {
+            PLS;
+            PVDE;
+            PVME1;
+            PVME2;
+            PVME3;
+            PVME4;
+        }
+        SR2 {
+            PVDO;
+            PVMO1;
+            PVMO2;
+            PVMO3;
+            PVMO4;
+        }
+    }
+
+    EXTI {
+        IMR1 {
+            IM16;
+        }
+        RTSR1 {
+            RT16;
+        }
+        FTSR1 {
+            FT16;
+        }
+        PR1 {
+            PIF16;
+        }
+        IMR2 {
+            IM35;
+            IM36;
+            IM37;
+            IM38;
+        }
+        RTSR2 {
+            RT35;
+            RT36;
+            RT37;
+            RT38;
+        }
+        FTSR2 {
+            FT35;
+            FT36;
+            FT37;
+            FT38;
+        }
+        PR2 {
+            PIF35;
+            PIF36;
+            PIF37;
+            PIF38;
+        }
+    }
+}
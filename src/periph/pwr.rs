@@ -0,0 +1,85 @@
+//! Power control.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts PWR register tokens.
+    pub macro periph_pwr;
+
+    /// PWR peripheral.
+    pub struct PwrPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::pwr;
+
+    RCC {
+        APB1ENR1 {
+            PWREN;
+        }
+        APB1RSTR1 {
+            PWRRST;
+        }
+        APB1SMENR1 {
+            PWRSMEN;
+        }
+    }
+
+    PWR {
+        CR3 {
+            EWUP1;
+            EWUP2;
+            EWUP3;
+            EWUP4;
+            EWUP5;
+            RRS;
+            APC;
+        }
+        CR4 {
+            WP1;
+            WP2;
+            WP3;
+            WP4;
+            WP5;
+        }
+        SR1 {
+            SBF;
+            WUF1;
+            WUF2;
+            WUF3;
+            WUF4;
+            WUF5;
+        }
+        SCR {
+            CSBF;
+            CWUF1;
+            CWUF2;
+            CWUF3;
+            CWUF4;
+            CWUF5;
+        }
+        PUCRA;
+        PUCRB;
+        PUCRC;
+        PUCRD;
+        PUCRE;
+        PUCRF;
+        PUCRG;
+        PUCRH;
+        PUCRI;
+        PDCRA;
+        PDCRB;
+        PDCRC;
+        PDCRD;
+        PDCRE;
+        PDCRF;
+        PDCRG;
+        PDCRH;
+        PDCRI;
+    }
+
+    SCB {
+        SCR {
+            SLEEPDEEP;
+        }
+    }
+}
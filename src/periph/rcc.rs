@@ -14,18 +14,13 @@ periph::singular! {
 
     RCC {
         CFGR;
-        APB1ENR1;
+        CSR;
     }
 
     PWR {
         CR1 {
-            DBP;
             LPMS;
         }
-        CR4 {
-            VBRS;
-            VBE;
-        }
     }
 
 }
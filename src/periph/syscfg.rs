@@ -0,0 +1,20 @@
+//! System configuration controller.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts SYSCFG register tokens.
+    pub macro periph_syscfg;
+
+    /// SYSCFG peripheral.
+    pub struct SyscfgPeriph;
+
+    drone_stm32_map::reg;
+    crate::periph::syscfg;
+
+    RCC {
+        APB2ENR {
+            SYSCFGEN;
+        }
+    }
+}
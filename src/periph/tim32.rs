@@ -0,0 +1,57 @@
+//! 32 bit general-purpose timers.
+
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts the TIM2 register tokens.
+    pub macro periph_tim32_tim2;
+
+    /// TIM2 peripheral.
+    pub struct Tim32Tim2Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::tim32;
+
+    RCC {
+        APB1ENR1 {
+            TIM2EN;
+        }
+    }
+
+    TIM2 {
+        CR1;
+        DIER;
+        SR;
+        EGR;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
+
+periph::singular! {
+    /// Extracts the TIM5 register tokens.
+    pub macro periph_tim32_tim5;
+
+    /// TIM5 peripheral.
+    pub struct Tim32Tim5Periph;
+
+    drone_stm32_map::reg;
+    crate::periph::tim32;
+
+    RCC {
+        APB1ENR1 {
+            TIM5EN;
+        }
+    }
+
+    TIM5 {
+        CR1;
+        DIER;
+        SR;
+        EGR;
+        CNT;
+        PSC;
+        ARR;
+    }
+}
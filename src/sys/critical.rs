@@ -0,0 +1,24 @@
+//! Critical sections.
+
+/// Runs `f` with interrupts masked (PRIMASK), restoring the previous state
+/// afterwards.
+#[cfg(not(feature = "std"))]
+pub fn free<R>(f: impl FnOnce() -> R) -> R {
+    let primask: u32;
+    unsafe {
+        llvm_asm!("mrs $0, primask" : "=r"(primask) ::: "volatile");
+        llvm_asm!("cpsid i" :::: "volatile");
+    }
+    let result = f();
+    if primask & 1 == 0 {
+        unsafe { llvm_asm!("cpsie i" :::: "volatile") };
+    }
+    result
+}
+
+/// Runs `f` with interrupts masked (PRIMASK), restoring the previous state
+/// afterwards.
+#[cfg(feature = "std")]
+pub fn free<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
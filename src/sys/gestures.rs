@@ -0,0 +1,377 @@
+//! Button gesture recognizer.
+//!
+//! A pure state machine fed with the sampled button level at each edge and
+//! its timestamp in milliseconds. It does not touch any hardware, so it
+//! runs on the host as well.
+
+/// Gesture timing thresholds, in milliseconds.
+#[derive(Clone, Copy, Debug)]
+pub struct GestureConfig {
+    /// Time the level must stay stable before a change is accepted. Shorter
+    /// pulses are contact bounces and ignored.
+    pub debounce_ms: u64,
+    /// Maximum time between the first release and the second press of a
+    /// double click. A single click is reported once it has elapsed.
+    pub double_click_ms: u64,
+    /// Press duration after which a long press is reported.
+    pub long_press_ms: u64,
+    /// Interval of the hold repeats after a long press. Zero disables them.
+    pub hold_repeat_ms: u64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 20,
+            double_click_ms: 300,
+            long_press_ms: 800,
+            hold_repeat_ms: 500,
+        }
+    }
+}
+
+/// Recognized gesture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gesture {
+    /// A short press, not followed by a second one in time.
+    Click,
+    /// Two short presses in a row.
+    DoubleClick,
+    /// The button has been held down for the long press threshold.
+    LongPress,
+    /// The button is still held down after a long press. Counts from 1.
+    Hold(u32),
+    /// The button has been released after a long press.
+    Release,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Idle,
+    Pressed { since: u64, second: bool },
+    Held { next_repeat: u64, repeats: u32 },
+    Released { at: u64 },
+}
+
+/// Button gesture state machine.
+#[derive(Clone, Debug)]
+pub struct ButtonGestures {
+    config: GestureConfig,
+    state: State,
+    pressed: bool,
+    level: bool,
+    level_since: Option<u64>,
+}
+
+impl ButtonGestures {
+    /// Creates a new recognizer with the button released.
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            state: State::Idle,
+            pressed: false,
+            level: false,
+            level_since: None,
+        }
+    }
+
+    /// Returns the thresholds.
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Returns `true` if the button is considered down.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Feeds an edge at `now`: `level` is the button level sampled after
+    /// the edge, `true` when pressed.
+    ///
+    /// The level is accepted once it has been stable for the debounce time,
+    /// at a later [`poll`](Self::poll) if needed.
+    pub fn edge(&mut self, level: bool, now: u64) -> Option<Gesture> {
+        self.level = level;
+        self.level_since = Some(now);
+        self.poll(now)
+    }
+
+    /// Reports the gestures due to time passing. Must be called at
+    /// [`deadline`](Self::deadline) at the latest.
+    pub fn poll(&mut self, now: u64) -> Option<Gesture> {
+        if let Some(since) = self.level_since {
+            if now.saturating_sub(since) >= self.config.debounce_ms {
+                self.level_since = None;
+                if self.level != self.pressed {
+                    self.pressed = self.level;
+                    let gesture = if self.pressed {
+                        self.press(since)
+                    } else {
+                        self.release(since)
+                    };
+                    if gesture.is_some() {
+                        return gesture;
+                    }
+                }
+            }
+        }
+        match self.state {
+            State::Pressed { since, .. }
+                if now.saturating_sub(since) >= self.config.long_press_ms =>
+            {
+                self.state = State::Held {
+                    next_repeat: since + self.config.long_press_ms + self.config.hold_repeat_ms,
+                    repeats: 0,
+                };
+                Some(Gesture::LongPress)
+            }
+            State::Held {
+                next_repeat,
+                repeats,
+            } if self.config.hold_repeat_ms > 0 && now >= next_repeat => {
+                let repeats = repeats + 1;
+                self.state = State::Held {
+                    next_repeat: next_repeat + self.config.hold_repeat_ms,
+                    repeats,
+                };
+                Some(Gesture::Hold(repeats))
+            }
+            State::Released { at } if now.saturating_sub(at) > self.config.double_click_ms => {
+                self.state = State::Idle;
+                Some(Gesture::Click)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the time at which [`poll`](Self::poll) reports the next
+    /// gesture if no edge comes in.
+    pub fn deadline(&self) -> Option<u64> {
+        let settle = self
+            .level_since
+            .map(|since| since + self.config.debounce_ms);
+        let gesture = match self.state {
+            State::Idle => None,
+            State::Pressed { since, .. } => Some(since + self.config.long_press_ms),
+            State::Held { next_repeat, .. } => {
+                if self.config.hold_repeat_ms > 0 {
+                    Some(next_repeat)
+                } else {
+                    None
+                }
+            }
+            State::Released { at } => Some(at + self.config.double_click_ms + 1),
+        };
+        match (settle, gesture) {
+            (Some(settle), Some(gesture)) => Some(settle.min(gesture)),
+            (settle, gesture) => settle.or(gesture),
+        }
+    }
+
+    fn press(&mut self, now: u64) -> Option<Gesture> {
+        match self.state {
+            State::Released { at } if now.saturating_sub(at) <= self.config.double_click_ms => {
+                self.state = State::Pressed {
+                    since: now,
+                    second: true,
+                };
+                None
+            }
+            State::Released { .. } => {
+                // The window was missed by the caller.
+                self.state = State::Pressed {
+                    since: now,
+                    second: false,
+                };
+                Some(Gesture::Click)
+            }
+            _ => {
+                self.state = State::Pressed {
+                    since: now,
+                    second: false,
+                };
+                None
+            }
+        }
+    }
+
+    fn release(&mut self, now: u64) -> Option<Gesture> {
+        match self.state {
+            State::Pressed { since, .. }
+                if now.saturating_sub(since) >= self.config.long_press_ms =>
+            {
+                // The long press was not polled in time.
+                self.state = State::Idle;
+                Some(Gesture::Release)
+            }
+            State::Pressed { second: true, .. } => {
+                self.state = State::Idle;
+                Some(Gesture::DoubleClick)
+            }
+            State::Pressed { second: false, .. } => {
+                if self.config.double_click_ms == 0 {
+                    self.state = State::Idle;
+                    Some(Gesture::Click)
+                } else {
+                    self.state = State::Released { at: now };
+                    None
+                }
+            }
+            State::Held { .. } => {
+                self.state = State::Idle;
+                Some(Gesture::Release)
+            }
+            State::Idle | State::Released { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gestures() -> ButtonGestures {
+        ButtonGestures::new(GestureConfig::default())
+    }
+
+    /// Feeds a clean edge and lets it settle.
+    fn edge(g: &mut ButtonGestures, level: bool, now: u64) -> Option<Gesture> {
+        assert_eq!(g.edge(level, now), None);
+        assert_eq!(g.deadline().map(|d| d <= now + 20), Some(true));
+        g.poll(now + 20)
+    }
+
+    #[test]
+    fn debounce() {
+        let mut g = gestures();
+        assert_eq!(g.edge(true, 0), None);
+        assert_eq!(g.edge(false, 10), None);
+        assert_eq!(g.edge(true, 15), None);
+        assert!(!g.is_pressed());
+        assert_eq!(g.deadline(), Some(35));
+        assert_eq!(g.poll(34), None);
+        assert!(!g.is_pressed());
+        assert_eq!(g.poll(35), None);
+        assert!(g.is_pressed());
+        // The press is timed from the last bounce.
+        assert_eq!(g.deadline(), Some(815));
+    }
+
+    #[test]
+    fn short_tap_is_ignored() {
+        let mut g = gestures();
+        assert_eq!(g.edge(true, 0), None);
+        assert_eq!(g.edge(false, 10), None);
+        assert_eq!(g.deadline(), Some(30));
+        assert_eq!(g.poll(30), None);
+        assert!(!g.is_pressed());
+        assert_eq!(g.deadline(), None);
+        assert_eq!(g.poll(5_000), None);
+    }
+
+    #[test]
+    fn bounce_ending_released() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        // Edges are missed: the last one reports the pressed level again,
+        // then the contact opens without a further edge being seen.
+        assert_eq!(g.edge(true, 100), None);
+        assert_eq!(g.edge(false, 105), None);
+        assert_eq!(g.poll(125), None);
+        assert!(!g.is_pressed());
+        // The release is timed from the last edge.
+        assert_eq!(g.deadline(), Some(406));
+        assert_eq!(g.poll(406), Some(Gesture::Click));
+    }
+
+    #[test]
+    fn click() {
+        let mut g = gestures();
+        assert_eq!(g.deadline(), None);
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(g.deadline(), Some(800));
+        assert_eq!(edge(&mut g, false, 100), None);
+        assert_eq!(g.deadline(), Some(401));
+        assert_eq!(g.poll(400), None);
+        assert_eq!(g.poll(401), Some(Gesture::Click));
+        assert_eq!(g.deadline(), None);
+        assert_eq!(g.poll(1_000), None);
+    }
+
+    #[test]
+    fn double_click_inside_window() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(edge(&mut g, false, 100), None);
+        assert_eq!(edge(&mut g, true, 400), None);
+        assert_eq!(g.deadline(), Some(1_200));
+        assert_eq!(edge(&mut g, false, 500), Some(Gesture::DoubleClick));
+        assert_eq!(g.deadline(), None);
+        assert_eq!(g.poll(2_000), None);
+    }
+
+    #[test]
+    fn double_click_outside_window() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(edge(&mut g, false, 100), None);
+        // Not polled at the deadline: the pending click comes with the edge.
+        assert_eq!(g.edge(true, 401), Some(Gesture::Click));
+        assert_eq!(g.poll(421), None);
+        assert!(g.is_pressed());
+        assert_eq!(edge(&mut g, false, 500), None);
+        assert_eq!(g.deadline(), Some(801));
+        assert_eq!(g.poll(801), Some(Gesture::Click));
+    }
+
+    #[test]
+    fn long_press_hold_release() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(g.poll(799), None);
+        assert_eq!(g.poll(800), Some(Gesture::LongPress));
+        assert_eq!(g.deadline(), Some(1_300));
+        assert_eq!(g.poll(1_299), None);
+        assert_eq!(g.poll(1_300), Some(Gesture::Hold(1)));
+        assert_eq!(g.deadline(), Some(1_800));
+        assert_eq!(g.poll(1_800), Some(Gesture::Hold(2)));
+        assert_eq!(g.deadline(), Some(2_300));
+        assert_eq!(edge(&mut g, false, 2_000), Some(Gesture::Release));
+        assert_eq!(g.deadline(), None);
+        assert_eq!(g.poll(3_000), None);
+    }
+
+    #[test]
+    fn hold_repeat_cadence_is_fixed() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(g.poll(800), Some(Gesture::LongPress));
+        // A late poll does not shift the following repeats.
+        assert_eq!(g.poll(1_450), Some(Gesture::Hold(1)));
+        assert_eq!(g.deadline(), Some(1_800));
+        assert_eq!(g.poll(1_800), Some(Gesture::Hold(2)));
+    }
+
+    #[test]
+    fn hold_repeats_disabled() {
+        let mut g = ButtonGestures::new(GestureConfig {
+            hold_repeat_ms: 0,
+            ..GestureConfig::default()
+        });
+        assert_eq!(edge(&mut g, true, 0), None);
+        assert_eq!(g.poll(800), Some(Gesture::LongPress));
+        assert_eq!(g.deadline(), None);
+        assert_eq!(g.poll(5_000), None);
+        assert_eq!(edge(&mut g, false, 5_000), Some(Gesture::Release));
+    }
+
+    #[test]
+    fn release_after_unpolled_long_press() {
+        let mut g = gestures();
+        assert_eq!(edge(&mut g, true, 0), None);
+        // The long press is still due while the release settles.
+        assert_eq!(g.edge(false, 900), Some(Gesture::LongPress));
+        assert_eq!(g.poll(920), Some(Gesture::Release));
+        assert_eq!(g.deadline(), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Low-power idle executor.
+
+use crate::tasks::root::SystemRes;
+use core::{
+    future::Future,
+    ptr,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+use drone_cortexm::processor;
+use futures::pin_mut;
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(waker_clone, waker_wake, waker_wake, waker_drop);
+
+/// Mode entered while no fiber is ready.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleMode {
+    /// Sleep mode: the core stops, all clocks keep running.
+    Sleep,
+    /// Stop 2 mode: all high speed clocks stop, only LSE based peripherals
+    /// (LPTIM1, RTC) and EXTI can wake up the core.
+    Stop2,
+}
+
+impl IdleMode {
+    /// Returns the deepest mode compatible with the clock configuration.
+    ///
+    /// The core wakes up from Stop 2 on the MSI with its previous range, so
+    /// Stop 2 is only used when the MSI is the system clock. A PLL or HSI16
+    /// based system clock would need to be restored after each wakeup.
+    pub fn for_clock(res: &SystemRes) -> Self {
+        if res.clksrc == 0b00 && res.pllsrc == 0b00 {
+            Self::Stop2
+        } else {
+            Self::Sleep
+        }
+    }
+}
+
+/// Idle executor extension for futures.
+pub trait FutureIdleExt: Future {
+    /// Runs the future to completion on the current thread, putting the core
+    /// into `mode` whenever the future is pending.
+    ///
+    /// Pending futures must be woken up by an interrupt, e.g. the LPTIM1
+    /// deadline, an EXTI line or any other peripheral.
+    fn idle_wait(self, res: &SystemRes, mode: IdleMode) -> Self::Output;
+}
+
+impl<F: Future> FutureIdleExt for F {
+    fn idle_wait(self, res: &SystemRes, mode: IdleMode) -> Self::Output {
+        let waker = unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) };
+        let mut cx = Context::from_waker(&waker);
+        let future = self;
+        pin_mut!(future);
+        poll_idle(res, mode, || match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => Some(output),
+            Poll::Pending => None,
+        })
+    }
+}
+
+/// Puts the core into `mode` until the next event: an interrupt, a waker or
+/// an EXTI line in event mode, which wakes the core without running any
+/// handler.
+pub fn wait_for_event(res: &SystemRes, mode: IdleMode) {
+    if mode == IdleMode::Stop2 {
+        // 010: Stop 2 mode.
+        res.rcc.write_pwr_cr1_lpms(0b010);
+    }
+    res.pwr.set_deep_sleep(mode == IdleMode::Stop2);
+    processor::wait_for_event();
+    res.pwr.set_deep_sleep(false);
+}
+
+/// Calls `f` until it returns a value, putting the core into `mode` in
+/// between.
+///
+/// The event register set by a wakeup in between makes WFE return
+/// immediately, so no wakeup can be lost after `f` has returned `None`.
+pub fn poll_idle<T>(res: &SystemRes, mode: IdleMode, mut f: impl FnMut() -> Option<T>) -> T {
+    loop {
+        if let Some(output) = f() {
+            return output;
+        }
+        wait_for_event(res, mode);
+    }
+}
+
+unsafe fn waker_clone(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn waker_wake(_data: *const ()) {
+    processor::send_event();
+}
+
+unsafe fn waker_drop(_data: *const ()) {}
@@ -3,5 +3,9 @@
 #[macro_use]
 pub mod system;
 
-#[macro_use]
-pub mod gpio_pins;
+pub mod critical;
+pub mod gestures;
+pub mod idle;
+pub mod monotonic;
+pub mod reset_cause;
+pub mod timer;
@@ -0,0 +1,190 @@
+//! Monotonic time, independent of the system clock.
+//!
+//! Time is counted in microseconds by the LPTIM1 time base, which is clocked
+//! from the LSE and keeps its pace across clock tree changes and in Stop 2.
+
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+/// A span of time, in microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration {
+    micros: u64,
+}
+
+/// A point in time, in microseconds since the time base has been started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant {
+    micros: u64,
+}
+
+/// A source of [`Instant`]s.
+pub trait Monotonic {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns the time elapsed since `earlier`.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+impl Duration {
+    /// Zero duration.
+    pub const ZERO: Self = Self::from_micros(0);
+
+    /// Creates a new [`Duration`] of `micros` microseconds.
+    #[inline]
+    pub const fn from_micros(micros: u64) -> Self {
+        Self { micros }
+    }
+
+    /// Creates a new [`Duration`] of `millis` milliseconds.
+    #[inline]
+    pub const fn from_millis(millis: u64) -> Self {
+        Self::from_micros(millis * 1_000)
+    }
+
+    /// Creates a new [`Duration`] of `secs` seconds.
+    #[inline]
+    pub const fn from_secs(secs: u64) -> Self {
+        Self::from_micros(secs * 1_000_000)
+    }
+
+    /// Returns the number of whole microseconds.
+    #[inline]
+    pub const fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// Returns the number of whole milliseconds.
+    #[inline]
+    pub const fn as_millis(self) -> u64 {
+        self.micros / 1_000
+    }
+
+    /// Returns `self - other`, or `None` if `other` is longer.
+    #[inline]
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.micros.checked_sub(other.micros).map(Self::from_micros)
+    }
+
+    /// Returns `self - other`, or zero if `other` is longer.
+    #[inline]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::from_micros(self.micros.saturating_sub(other.micros))
+    }
+}
+
+impl Instant {
+    /// Creates a new [`Instant`] `micros` microseconds after the start of the
+    /// time base.
+    #[inline]
+    pub const fn from_micros(micros: u64) -> Self {
+        Self { micros }
+    }
+
+    /// Returns the number of microseconds since the start of the time base.
+    #[inline]
+    pub const fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// Returns the number of milliseconds since the start of the time base.
+    #[inline]
+    pub const fn as_millis(self) -> u64 {
+        self.micros / 1_000
+    }
+
+    /// Returns the time elapsed from `earlier` to `self`, or zero if
+    /// `earlier` is later.
+    #[inline]
+    pub fn saturating_duration_since(self, earlier: Self) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(earlier.micros))
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::from_micros(self.micros + rhs.micros)
+    }
+}
+
+impl AddAssign for Duration {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting durations")
+    }
+}
+
+impl SubAssign for Duration {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: u32) -> Self {
+        Self::from_micros(self.micros * u64::from(rhs))
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: u32) -> Self {
+        Self::from_micros(self.micros / u64::from(rhs))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Duration) -> Self {
+        Self::from_micros(self.micros + rhs.micros)
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    #[inline]
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Duration) -> Self {
+        Self::from_micros(self.micros.saturating_sub(rhs.micros))
+    }
+}
+
+impl Sub for Instant {
+    type Output = Duration;
+
+    /// Saturates at zero, see [`Instant::saturating_duration_since`].
+    #[inline]
+    fn sub(self, rhs: Self) -> Duration {
+        self.saturating_duration_since(rhs)
+    }
+}
@@ -0,0 +1,71 @@
+//! Reset cause detection.
+
+use crate::drv::pwr::WakeupPin;
+use crate::tasks::root::SystemRes;
+
+/// The reason of the last reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetCause {
+    /// Wakeup from Standby mode, with the wakeup pin if one was involved.
+    Standby(Option<WakeupPin>),
+    /// Firewall reset.
+    Firewall,
+    /// Reset after an option byte load.
+    OptionByteLoad,
+    /// Window watchdog reset.
+    WindowWatchdog,
+    /// Independent watchdog reset.
+    IndependentWatchdog,
+    /// Illegal Stop, Standby or Shutdown mode entry.
+    LowPower,
+    /// Software reset (SYSRESETREQ).
+    Software,
+    /// Brown-out or power-on reset.
+    BrownOut,
+    /// External reset through the NRST pin.
+    Pin,
+    /// No reset flag was set.
+    Unknown,
+}
+
+impl ResetCause {
+    /// Reads the reset cause and clears the flags, so that the next reset
+    /// is reported on its own.
+    ///
+    /// This should be called before anything else touches RCC_CSR or
+    /// PWR_SR1. The PWR clock must be held.
+    pub fn read(res: &SystemRes) -> Self {
+        let flags = res.rcc.read_reset_flags();
+        let cause = if res.pwr.woke_from_standby() {
+            Self::Standby(res.pwr.wakeup_pin())
+        } else if flags.firewall {
+            Self::Firewall
+        } else if flags.option_byte {
+            Self::OptionByteLoad
+        } else if flags.window_watchdog {
+            Self::WindowWatchdog
+        } else if flags.independent_watchdog {
+            Self::IndependentWatchdog
+        } else if flags.low_power {
+            Self::LowPower
+        } else if flags.software {
+            Self::Software
+        } else if flags.brown_out {
+            Self::BrownOut
+        } else if flags.pin {
+            // Checked last: NRST is also driven low by all internal resets.
+            Self::Pin
+        } else {
+            Self::Unknown
+        };
+        res.rcc.clear_reset_flags();
+        res.pwr.clear_standby_flags();
+        cause
+    }
+
+    /// Returns `true` if the device was reset by one of the watchdogs.
+    #[inline]
+    pub fn is_watchdog(&self) -> bool {
+        matches!(self, Self::WindowWatchdog | Self::IndependentWatchdog)
+    }
+}
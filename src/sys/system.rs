@@ -2,16 +2,15 @@
 
 use crate::consts::{HSE_CLK, HSI16_CLK};
 use crate::tasks::root::SystemRes;
-use crate::thr;
+use core::sync::atomic::{AtomicU32, Ordering};
 use drone_core::log;
 use drone_cortexm::swo;
-use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
-use drone_stm32_map::periph::sys_tick::SysTickPeriph;
-use futures::prelude::*;
 
-/// An error returned when a receiver has missed too many ticks.
-#[derive(Debug)]
-pub struct TickOverflow;
+/// The HCLK frequency of the current clock configuration.
+static HCLK: AtomicU32 = AtomicU32::new(4_000_000);
+
+/// The APB1 timer clock frequency of the current clock configuration.
+static APB1_TIM_CLK: AtomicU32 = AtomicU32::new(4_000_000);
 
 /// System.
 pub struct System {}
@@ -41,22 +40,52 @@ impl System {
             res.pll.init(res);
             res.pll.enable();
         }
+        let hclk = Self::calculate_hclk(res);
+        HCLK.store(hclk, Ordering::Release);
+        APB1_TIM_CLK.store(Self::calculate_apb1_tim_clk(res, hclk), Ordering::Release);
         swo::flush();
-        swo::update_prescaler(Self::calculate_hclk(res) / log::baud_rate!() - 1);
+        swo::update_prescaler(hclk / log::baud_rate!() - 1);
         res.flash.set_latency(Self::calculate_latency(res));
     }
 
     /// Resets the RCC.
     pub fn reset_rcc(res: &SystemRes) {
         res.rcc.reset();
-        res.lse.reset();
+        // The LSE keeps running: it clocks the LPTIM1 time base.
         res.pll.reset();
         res.msi.reset();
         res.hsi16.reset();
+        HCLK.store(4_000_000, Ordering::Release);
+        APB1_TIM_CLK.store(Self::calculate_apb1_tim_clk(res, 4_000_000), Ordering::Release);
         swo::flush();
         swo::update_prescaler(4_000_000 / log::baud_rate!() - 1);
     }
 
+    /// Returns the HCLK frequency set by the last [`System::apply_clock_config`]
+    /// or [`System::reset_rcc`].
+    #[inline]
+    pub fn hclk() -> u32 {
+        HCLK.load(Ordering::Acquire)
+    }
+
+    /// Returns the clock frequency of the timers on APB1, e.g. TIM2 and TIM5.
+    #[inline]
+    pub fn apb1_tim_clk() -> u32 {
+        APB1_TIM_CLK.load(Ordering::Acquire)
+    }
+
+    /// Calculates the APB1 timer clock from `hclk`: the timers run at twice
+    /// the APB1 clock when the APB1 prescaler divides it.
+    pub fn calculate_apb1_tim_clk(res: &SystemRes, hclk: u32) -> u32 {
+        let ppre1 = res.rcc.read_ppre1();
+        if ppre1 & 0b100 == 0 {
+            hclk
+        } else {
+            // 100: /2, 101: /4, 110: /8, 111: /16.
+            (hclk >> (ppre1 - 0b011)) * 2
+        }
+    }
+
     /// Set flash read access latency.
     // To correctly read data from Flash memory, the number of
     // wait states (LATENCY) must be correctly programmed
@@ -175,24 +204,4 @@ impl System {
         }
         _hclk
     }
-
-    /// Millisecond delay.
-    pub async fn delay(
-        millis: u32,
-        hclk: u32,
-        sys_tick: &SysTickPeriph,
-        thr_sys_tick: thr::SysTick,
-    ) -> () {
-        let mut tick_stream = thr_sys_tick
-            .add_pulse_try_stream(|| Err(TickOverflow), fib::new_fn(|| fib::Yielded(Some(1))));
-        sys_tick.stk_val.store(|r| r.write_current(0));
-        sys_tick
-            .stk_load
-            .store(|r| r.write_reload(millis * (hclk / 8000)));
-        sys_tick.stk_ctrl.store(|r| {
-            r.set_tickint() // Counting down to 0 triggers the SysTick interrupt
-                .set_enable() // Start the counter in a multi-shot way
-        });
-        tick_stream.next().await;
-    }
 }
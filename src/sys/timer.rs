@@ -0,0 +1,235 @@
+//! Software timer queue.
+//!
+//! Any number of fibers can wait for their own deadlines. The deadlines are
+//! kept in one queue sorted by time, and a single hardware alarm is armed
+//! for the earliest one. The alarm interrupt must call
+//! [`Timer::service`].
+
+use crate::sys::{
+    critical,
+    monotonic::{Duration, Instant, Monotonic},
+};
+use alloc::vec::Vec;
+use core::{
+    cell::UnsafeCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use futures::prelude::*;
+use futures::{pin_mut, select_biased};
+
+/// Hardware alarm driving the timer queue.
+pub trait Alarm: Monotonic + Copy + Unpin + Send + Sync + 'static {
+    /// Arms the alarm for `deadline`. Returns `false` if the deadline is too
+    /// close to be armed, in which case it is considered as reached.
+    ///
+    /// Deadlines too far for the hardware may be armed later: the interrupt
+    /// must then call [`Timer::service`] periodically, e.g. on counter
+    /// overflows.
+    fn set(&self, deadline: Instant) -> bool;
+}
+
+/// The future returned by [`Timer::timeout`] did not complete in time.
+#[derive(Debug)]
+pub struct Elapsed;
+
+/// A copyable handle to the timer queue.
+#[derive(Clone, Copy)]
+pub struct Timer<A: Alarm> {
+    alarm: A,
+}
+
+/// Future completing at a deadline, see [`Timer::sleep_until`].
+pub struct Sleep<A: Alarm> {
+    alarm: A,
+    deadline: Instant,
+    id: Option<u32>,
+}
+
+/// Stream of periodic instants, see [`Timer::interval`].
+pub struct Interval<A: Alarm> {
+    sleep: Sleep<A>,
+    period: Duration,
+}
+
+struct Entry {
+    id: u32,
+    deadline: Instant,
+    waker: Waker,
+}
+
+struct Queue {
+    entries: UnsafeCell<Vec<Entry>>,
+    next_id: UnsafeCell<u32>,
+}
+
+// Only accessed inside critical sections.
+unsafe impl Sync for Queue {}
+
+static QUEUE: Queue = Queue {
+    entries: UnsafeCell::new(Vec::new()),
+    next_id: UnsafeCell::new(0),
+};
+
+impl<A: Alarm> Timer<A> {
+    /// Creates a new [`Timer`] handle. There is only one queue: all handles
+    /// must use the same alarm.
+    #[inline]
+    pub fn new(alarm: A) -> Self {
+        Self { alarm }
+    }
+
+    /// Returns the current time.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        self.alarm.now()
+    }
+
+    /// Returns a future completing at `deadline`.
+    pub fn sleep_until(&self, deadline: Instant) -> Sleep<A> {
+        Sleep {
+            alarm: self.alarm,
+            deadline,
+            id: None,
+        }
+    }
+
+    /// Returns a future completing after `duration`.
+    pub fn sleep(&self, duration: Duration) -> Sleep<A> {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Returns a stream yielding every `period`, starting one period from
+    /// now.
+    ///
+    /// Periods missed by a slow consumer are skipped.
+    pub fn interval(&self, period: Duration) -> Interval<A> {
+        assert!(period > Duration::ZERO, "zero interval period");
+        Interval {
+            sleep: self.sleep(period),
+            period,
+        }
+    }
+
+    /// Runs `future` for at most `duration`.
+    pub async fn timeout<F: Future>(
+        &self,
+        future: F,
+        duration: Duration,
+    ) -> Result<F::Output, Elapsed> {
+        let future = future.fuse();
+        let sleep = self.sleep(duration).fuse();
+        pin_mut!(future, sleep);
+        select_biased! {
+            output = future => Ok(output),
+            () = sleep => Err(Elapsed),
+        }
+    }
+
+    /// Wakes the expired sleepers and arms the alarm for the next deadline.
+    /// Called from the alarm interrupt.
+    pub fn service(&self) {
+        critical::free(|| {
+            let entries = unsafe { &mut *QUEUE.entries.get() };
+            let now = self.alarm.now();
+            while let Some(entry) = entries.first() {
+                if entry.deadline > now && self.alarm.set(entry.deadline) {
+                    break;
+                }
+                entries.remove(0).waker.wake();
+            }
+        });
+    }
+}
+
+impl<A: Alarm> Sleep<A> {
+    /// Returns the deadline.
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl<A: Alarm> Future for Sleep<A> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        let (alarm, deadline) = (this.alarm, this.deadline);
+        let id = &mut this.id;
+        let first = critical::free(|| {
+            let entries = unsafe { &mut *QUEUE.entries.get() };
+            if let Some(queued) = *id {
+                // An entry removed from the queue has expired.
+                return entries
+                    .iter_mut()
+                    .find(|entry| entry.id == queued)
+                    .map(|entry| {
+                        if !entry.waker.will_wake(cx.waker()) {
+                            entry.waker = cx.waker().clone();
+                        }
+                        false
+                    });
+            }
+            if alarm.now() >= deadline {
+                return None;
+            }
+            let next_id = unsafe { &mut *QUEUE.next_id.get() };
+            let entry = Entry {
+                id: *next_id,
+                deadline,
+                waker: cx.waker().clone(),
+            };
+            *id = Some(*next_id);
+            *next_id = next_id.wrapping_add(1);
+            let position = entries
+                .iter()
+                .position(|entry| entry.deadline > deadline)
+                .unwrap_or_else(|| entries.len());
+            entries.insert(position, entry);
+            Some(position == 0)
+        });
+        match first {
+            None => Poll::Ready(()),
+            Some(first) => {
+                if first {
+                    Timer::new(alarm).service();
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<A: Alarm> Drop for Sleep<A> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            critical::free(|| {
+                let entries = unsafe { &mut *QUEUE.entries.get() };
+                entries.retain(|entry| entry.id != id);
+            });
+        }
+    }
+}
+
+impl<A: Alarm> Stream for Interval<A> {
+    type Item = Instant;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        let this = &mut *self;
+        match Pin::new(&mut this.sleep).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(()) => {
+                let at = this.sleep.deadline;
+                let now = this.sleep.alarm.now();
+                let mut next = at + this.period;
+                while next <= now {
+                    next += this.period;
+                }
+                this.sleep = Timer::new(this.sleep.alarm).sleep_until(next);
+                Poll::Ready(Some(at))
+            }
+        }
+    }
+}
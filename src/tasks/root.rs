@@ -1,36 +1,44 @@
 //! The root task.
 
 use crate::{
+    board::Board,
     drv::{
-        exti::{ExtiDrv, ExtiSetup},
+        backup::{Backup, VbatCharging},
+        button::ButtonDrv,
+        clock_gate,
         flash::Flash,
-        gpio::GpioHead,
         hsi16::Hsi16,
+        lptim::{LptimDrv, LptimSetup},
         lse::Lse,
         msi::Msi,
         pll::Pll,
+        pvd::{PvdDrv, PvdLevel, PvdSetup, PvmMonitors, SupplyEvent},
+        pwr::Pwr,
         rcc::Rcc,
     },
-    drv_gpio_pins,
-    sys::{gpio_pins::GpioPins, system::System},
+    drv_board,
+    sys::{
+        gestures::{Gesture, GestureConfig},
+        idle::{FutureIdleExt, IdleMode},
+        monotonic::Duration,
+        reset_cause::ResetCause,
+        system::System,
+    },
     thr,
-    thr::{Thrs, ThrsInit},
+    thr::ThrsInit,
     Regs,
 };
 
 use drone_cortexm::processor::fpu_init;
-use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
-use drone_stm32_map::periph::exti::periph_exti13;
-use drone_stm32_map::periph::exti::Exti13;
-use drone_stm32_map::periph::gpio::{periph_gpio_b_head, periph_gpio_c_head};
-use drone_stm32_map::periph::sys_tick::{periph_sys_tick, SysTickPeriph};
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
 
 use futures::prelude::*;
 use futures::select_biased;
 
 enum Event {
     Tick,
-    Push,
+    Button(Gesture),
+    Supply(SupplyEvent),
 }
 
 enum ClockMode {
@@ -40,16 +48,14 @@ enum ClockMode {
     Full80MHz,
 }
 
-/// An error returned when a receiver has missed too many ticks.
-#[derive(Debug)]
-pub struct TickOverflow;
-
 /// System Resources
 pub struct SystemRes {
     pub pll: Pll,
     pub hsi16: Hsi16,
     pub msi: Msi,
     pub lse: Lse,
+    pub backup: Backup,
+    pub pwr: Pwr,
     pub rcc: Rcc,
     pub flash: Flash,
     pub pllm: u32,
@@ -82,6 +88,10 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
         // The LSE crystal is a 32.768 kHz Low Speed External crystal or ceramic resonator.
         // It is available on the Nucleo board.
         lse: Lse::new(periph_lse!(reg)),
+        // The backup domain holds the LSE and the RTC.
+        backup: Backup::new(periph_backup!(reg)),
+        // The power controller, used for Standby mode and wakeup pins.
+        pwr: Pwr::new(periph_pwr!(reg)),
         // The RCC component.
         rcc: Rcc::new(periph_rcc!(reg)),
         // The flash component,
@@ -110,17 +120,33 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
         pllsrc: 0b00, // Field RCC_PLLCFGR_PLLSRC in ref. manual.
     };
 
-    // The on-board user LEDs are connected to GPIO banks B and C.
-    // Create register and pins mapping component.
-    let gpio_pins = drv_gpio_pins!(reg);
-    let mut gpio_b = GpioHead::new(periph_gpio_b_head!(reg));
-    let mut gpio_c = GpioHead::new(periph_gpio_c_head!(reg));
-    // Enable and initialize.
-    let gpio_b_en = gpio_b.enable();
-    let gpio_c_en = gpio_c.enable();
-    gpio_pins.init(gpio_b_en.inventory_token(), gpio_c_en.inventory_token());
-
-    let sys_tick = periph_sys_tick!(reg);
+    // The PWR registers are accessed all along, keep their clock on.
+    clock_gate::acquire(&res.pwr);
+
+    // Find out why we have been reset before anything else touches the
+    // reset flags.
+    let reset_cause = ResetCause::read(&res);
+    println!("reset cause {:?}", reset_cause);
+    if reset_cause.is_watchdog() {
+        println!("!! watchdog reset");
+    }
+
+    // The LSE lives in the backup domain, so its write protection must be
+    // lifted. VBAT charging stays off: on the NUCLEO, VBAT is tied to VDD and
+    // a coin cell fitted instead must never be charged.
+    res.backup.set_access(true);
+    res.backup.set_vbat_charging(VbatCharging::Disabled);
+
+    // Check whether we are coming back from Standby mode.
+    if let ResetCause::Standby(wakeup_pin) = reset_cause {
+        println!("woke up from standby by {:?}", wakeup_pin);
+        let retained = unsafe { res.pwr.retained() };
+        if retained.is_valid() {
+            println!("retained data {:?}", &retained.words[..4]);
+            retained.invalidate();
+        }
+    }
+
     let (thr, scb) = thr::init_extended(thr_init);
     thr.hard_fault.add_once(|| panic!("Hard Fault"));
 
@@ -129,25 +155,44 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
         fpu_init(true);
     }
 
-    // Enable the system configuration controller clock.
-    reg.rcc_apb2enr.syscfgen.set_bit();
-
     // Setup fault handlers.
     thr.hard_fault.add_once(|| panic!("Hard Fault"));
 
-    // Exti configuration for the user button.
-    let exti13 = ExtiDrv::init(ExtiSetup {
-        exti: periph_exti13!(reg),
-        exti_int: thr.exti_15_10,
-        config: 0b0010, // PC13 pin.
-        falling: false, // trigger the interrupt on a falling edge.
-        rising: true,   // don't trigger the interrupt on a rising edge.
-    });
+    // The on-board user LEDs and the user button, with its EXTI line.
+    let board = drv_board!(reg, thr);
+
+    // Supply supervision: get notified when VDD drops below 2.8 V.
+    let pvd = PvdDrv::init(
+        PvdSetup {
+            pvd: periph_pvd!(reg),
+            pvd_int: thr.pvd_pvm,
+            level: Some(PvdLevel::V2_8),
+            monitors: PvmMonitors::default(),
+        },
+        &res,
+    );
+    thr.pvd_pvm.enable_int();
+
+    // Start the LSE once: it clocks the LPTIM1 time base and keeps running
+    // across clock tree changes.
+    res.lse.init(&res);
+    let lptim = LptimDrv::init(
+        LptimSetup {
+            lptim: periph_lptim1!(reg),
+            lptim_int: thr.lptim_1,
+        },
+        &res,
+    );
+    for id in clock_gate::clocked() {
+        println!("clock {:?} on", id);
+    }
 
     'user_button_pressed: loop {
         // Reset the clock control registers to their default.
         System::reset_rcc(&res);
-        System::delay(20, 4_000_000, &sys_tick, thr.sys_tick).root_wait();
+        // LPTIM1 runs from the LSE, its delays do not depend on the clock
+        // tree.
+        lptim.sleep(Duration::from_millis(20)).root_wait();
 
         // Apply the current clock tree configuration.
         System::apply_clock_config(&res);
@@ -155,12 +200,13 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
         // Calculate the configured clock speed.
         let hclk = System::calculate_hclk(&res);
 
-        System::delay(20, hclk, &sys_tick, thr.sys_tick).root_wait();
+        lptim.sleep(Duration::from_millis(20)).root_wait();
 
         // Adapt SWO clock configuration to current speed.
         println!("speed {}", hclk);
 
-        listen(&sys_tick, &thr, thr.sys_tick, &exti13, &gpio_pins, hclk).root_wait();
+        // Sleep whenever there is nothing to do until the next deadline.
+        listen(&lptim, &pvd, &board, hclk).idle_wait(&res, IdleMode::for_clock(&res));
 
         // Set different configuration for the clock tree
         match clock_mode {
@@ -169,62 +215,51 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
                 res.pllsrc = 0b00; // PLL without input.
                 res.clksrc = 0b01; // Use HSI16 clock source.
                 res.msirange = 0b0110; // MSI reset value
-                gpio_pins.output(1, true);
-                gpio_pins.output(2, false);
+                board.set_all(true, false, board.red().is_on());
             }
             ClockMode::Slow16MHz => {
                 clock_mode = ClockMode::Medium48MHz; // <- new mode.
                 res.pllsrc = 0b00; // PLL without input.
                 res.clksrc = 0b00; // Use MSI clock source.
                 res.msirange = 0b1011; // MSI 48MHz mode.
-                gpio_pins.output(1, false);
-                gpio_pins.output(2, true);
+                board.set_all(false, true, board.red().is_on());
             }
             ClockMode::Medium48MHz => {
                 clock_mode = ClockMode::Full80MHz; // <- new mode.
                 res.pllsrc = 0b10; // HSI16 is PLL clock input.
                 res.clksrc = 0b11; // Use PLL output 80 MHz
                 res.msirange = 0b0110; // MSI reset value
-                gpio_pins.output(1, true);
-                gpio_pins.output(2, true);
+                board.set_all(true, true, board.red().is_on());
             }
             ClockMode::Full80MHz => {
                 clock_mode = ClockMode::Reset4MHz; // <- new mode.
                 res.pllsrc = 0b00; // PLL without input.
                 res.clksrc = 0b00; // Use MSI.
                 res.msirange = 0b0110; // MSI reset value 4MHz.
-                gpio_pins.output(1, false);
-                gpio_pins.output(2, false);
+                board.set_all(false, false, board.red().is_on());
             }
         }
     }
 }
 
 async fn listen(
-    sys_tick: &SysTickPeriph,
-    thr: &Thrs,
-    thr_sys_tick: thr::SysTick,
-    exti13: &ExtiDrv<Exti13, thr::Exti1510>,
-    gpio_pins: &GpioPins,
+    lptim: &LptimDrv<thr::Lptim1>,
+    pvd: &PvdDrv<thr::PvdPvm>,
+    board: &Board,
     hclk: u32,
-) -> Event {
+) {
     println!("enter listen");
-    // Attach a listener that will notify us on user button pressed.
-    let mut button_stream = exti13.create_saturating_stream();
-
-    // Attach a listener that will notify us on each SYS_TICK interrupt trigger.
-    let mut tick_stream = thr_sys_tick.add_pulse_try_stream(
-        // This closure will be called when a receiver no longer can store the
-        // number of ticks since the last stream poll. If this happens, a
-        // `TickOverflow` error will be sent over the stream as is final value.
-        || Err(TickOverflow),
-        // A fiber that will be called on each interrupt trigger. It sends a
-        // single tick over the stream.
-        fib::new_fn(|| fib::Yielded(Some(1))),
+    let exti13 = board.button().exti();
+    // Attach a listener that will notify us on user button gestures.
+    let mut button = ButtonDrv::new(
+        exti13.create_edge_stream(board.button().pin().probe(), lptim.clock()),
+        lptim,
+        GestureConfig::default(),
     );
 
-    // Clear the current value of the timer.
-    sys_tick.stk_val.store(|r| r.write_current(0));
+    // Attach a listener that will notify us on supply threshold crossings.
+    let mut supply_stream = pvd.create_saturating_stream();
+
     //
     // The duration of setting the led ON is inversely proportional to the
     // MCU clock speed. It shall be:
@@ -232,87 +267,53 @@ async fn listen(
     //   1.00 seconds when cpu clocks @ 16MHz
     //   0.33 seconds when cpu clocks @ 48MHz
     //   0.20 seconds when cpu clocks @ 80MHz
+    let blink_ival = Duration::from_millis(4_000) * 4_000_000 / hclk;
 
-    // The trigger is set so that it returns twice per interval
-    // at the highest speed, and proportionally more often per interval
-    // at lower speeds.
-    // That way, the Exti interrupt will happen every 100ms at all speeds
-    // and it can be used to for debounceing and doubleclick control.
-    let mut trigger = 4_000_000 / 8; // So many systick/sec at 4MHz.
-    trigger = trigger / 10; // So many in 100ms at 4MHz.
-    trigger = trigger * (hclk / 4_000_000); // More at higher speed
-
-    sys_tick.stk_load.store(|r| r.write_reload(trigger));
-    sys_tick.stk_ctrl.store(|r| {
-        r.set_tickint() // Counting down to 0 triggers the SysTick interrupt
-            .set_enable() // Start the counter in a multi-shot way
-    });
-
-    let mut red_led_on = true;
-    gpio_pins.output(3, true); // Start with red led ON.
+    board.red().set(true); // Start with red led ON.
 
-    // Enable the interrupt for the user button.
-    thr.exti_15_10.enable_int();
+    // Unmask the interrupt for the user button.
+    exti13.unmask();
 
-    // Counters
-    let mut debounce_protection: i16 = 0;
-    let mut doubleclick_protection: i16 = 0;
-    let mut ticks_cnt: u32 = 0;
-
-    // Monitored interval lengths (accumulated ticks).
-    let debounce_ival = 2;
-    let doubleclick_ival = 4;
-
-    // This is dependent on mcu speed:
-    let ticks_ival: u32 = 40 / (hclk / 4_000_000);
+    // Next red led toggle. The core sleeps in between.
+    let mut next_toggle = lptim.now() + blink_ival;
 
     'blinky: loop {
         let evt = select_biased! {
-            _p = button_stream.next().fuse() => Event::Push,
-            _t = tick_stream.next().fuse() => Event::Tick,
+            s = supply_stream.next().fuse() => Event::Supply(s.expect("supply stream ended")),
+            g = button.next(next_toggle).fuse() => g.map_or(Event::Tick, Event::Button),
         };
         match evt {
             Event::Tick => {
-                if debounce_protection > i16::MIN {
-                    debounce_protection = debounce_protection - 1;
-                };
-                if doubleclick_protection < i16::MAX {
-                    doubleclick_protection = doubleclick_protection + 1;
-                };
-                if debounce_protection == 0 && doubleclick_protection >= doubleclick_ival {
-                    break 'blinky;
-                }
-                // The low and the high interval is 'ticks_ival' ticks.
-                ticks_cnt = ticks_cnt + 1;
-                if ticks_cnt >= ticks_ival {
-                    ticks_cnt = 0;
-                    match red_led_on {
-                        true => {
-                            red_led_on = false;
-                            gpio_pins.output(3, false);
-                        }
-                        _ => {
-                            red_led_on = true;
-                            gpio_pins.output(3, true);
-                        }
-                    }
-                }
+                next_toggle += blink_ival;
+                board.red().toggle();
+            }
+            Event::Supply(SupplyEvent { supply, low: true }) => {
+                // This is the moment to flush any state to flash.
+                println!("!! {:?} below threshold", supply);
+            }
+            Event::Supply(SupplyEvent { supply, low: false }) => {
+                println!("{:?} recovered", supply);
+            }
+            Event::Button(Gesture::Click) => {
+                // A click is only reported once the button has been released
+                // and the double click window has elapsed, so the contacts
+                // have settled by now.
+                println!("--");
+                exti13.mask();
+                break 'blinky;
+            }
+            Event::Button(Gesture::DoubleClick) => {
+                println!("++");
+            }
+            Event::Button(Gesture::LongPress) => {
+                println!("long press");
+            }
+            Event::Button(Gesture::Hold(repeats)) => {
+                println!("hold {}", repeats);
             }
-            Event::Push => {
-                // After disabling the interrupt or after re-enabling 
-                // the interrupt, the stream needs to be flushed to protect 
-                // the logic during the switching period against mechanical 
-                // contact bouncing and doubleclicks.
-                if doubleclick_protection > doubleclick_ival {
-                    println!("--");
-                    thr.exti_15_10.disable_int();
-                    debounce_protection = debounce_ival;
-                } else {
-                    doubleclick_protection = 0;
-                    println!("++");
-                }
+            Event::Button(Gesture::Release) => {
+                println!("released");
             }
         }
     }
-    Event::Push
 }
@@ -29,10 +29,60 @@ thr! {
             pub sys_tick;
         };
         interrupts => {
+            /// PVD/PVM1/PVM2/PVM3/PVM4 through EXTI lines 16/35/36/37/38.
+            1: pub pvd_pvm;
+            /// Tamper and timestamp interrupts through EXTI line 19.
+            2: pub tamp_stamp;
+            /// RTC wakeup timer through EXTI line 20.
+            3: pub rtc_wkup;
             /// RCC global interrupt.
             5: pub rcc;
-            /// EXTI Line 13 interrupt.
-            23: pub exti15_10;
+            /// EXTI line 0 interrupt.
+            6: pub exti0;
+            /// EXTI line 1 interrupt.
+            7: pub exti1;
+            /// EXTI line 2 interrupt.
+            8: pub exti2;
+            /// EXTI line 3 interrupt.
+            9: pub exti3;
+            /// EXTI line 4 interrupt.
+            10: pub exti4;
+            /// EXTI lines 5 to 9 interrupt.
+            23: pub exti9_5;
+            /// TIM2 global interrupt.
+            28: pub tim2;
+            /// TIM3 global interrupt.
+            29: pub tim3;
+            /// TIM4 global interrupt.
+            30: pub tim4;
+            /// USART1 global interrupt, wakeup through EXTI line 26.
+            37: pub usart1;
+            /// USART2 global interrupt, wakeup through EXTI line 27.
+            38: pub usart2;
+            /// USART3 global interrupt, wakeup through EXTI line 28.
+            39: pub usart3;
+            /// EXTI lines 10 to 15 interrupt.
+            40: pub exti15_10;
+            /// RTC alarms through EXTI line 18.
+            41: pub rtc_alarm;
+            /// TIM8 update interrupt.
+            44: pub tim8_up;
+            /// TIM5 global interrupt.
+            50: pub tim5;
+            /// UART4 global interrupt, wakeup through EXTI line 29.
+            52: pub uart4;
+            /// UART5 global interrupt, wakeup through EXTI line 30.
+            53: pub uart5;
+            /// COMP1/COMP2 through EXTI lines 21/22.
+            64: pub comp;
+            /// LPTIM1 global interrupt, wakeup through EXTI line 32.
+            65: pub lptim1;
+            /// LPTIM2 global interrupt, wakeup through EXTI line 33.
+            66: pub lptim2;
+            /// USB OTG FS global interrupt, wakeup through EXTI line 17.
+            67: pub otg_fs;
+            /// LPUART1 global interrupt, wakeup through EXTI line 31.
+            70: pub lpuart1;
         };
     };
 }